@@ -1,104 +1,69 @@
-use crate::splits::SplitsFile;
+use crate::history::{AttemptHistory, Outcome, Policy};
+use crate::layout;
+use crate::locations::LogLocation;
+use crate::memory::{MemoryConfig, MemoryWatcher};
+use crate::packages::{discover_autosplitters, AvailableGame, BrokenGame};
+use crate::practice::Practice;
+use crate::session::{ManualAction, SessionRecorder};
+use crate::settings::{GameSettings, Preferences, SettingKind, SettingValue};
+use crate::splits::{display_name, SplitsFile};
+use crate::stats;
+use crate::theme::{DeltaKind, Rgb, Theme};
+use crate::wasm::WasmAutosplitter;
 use crate::watcher::{LogWatcher, WatchEvent};
 use eframe::egui;
-use livesplit_core::{Run, Segment, Timer, TimerPhase, TimeSpan};
-use serde::Deserialize;
-use std::path::PathBuf;
-
-const DARK_BG: egui::Color32 = egui::Color32::from_rgb(20, 20, 25);
-const HEADER_BG: egui::Color32 = egui::Color32::from_rgb(30, 30, 40);
-const SPLIT_BG: egui::Color32 = egui::Color32::from_rgb(25, 25, 35);
-const SPLIT_CURRENT_BG: egui::Color32 = egui::Color32::from_rgb(40, 40, 60);
-
-const TEXT_WHITE: egui::Color32 = egui::Color32::from_rgb(255, 255, 255);
-const TEXT_GRAY: egui::Color32 = egui::Color32::from_rgb(170, 170, 170);
-const TIME_GREEN: egui::Color32 = egui::Color32::from_rgb(50, 205, 50);
-const TIME_RED: egui::Color32 = egui::Color32::from_rgb(220, 60, 60);
-const TIME_GOLD: egui::Color32 = egui::Color32::from_rgb(255, 215, 0);
-const TIME_BLUE: egui::Color32 = egui::Color32::from_rgb(100, 149, 237);
-const ACCENT_COLOR: egui::Color32 = egui::Color32::from_rgb(139, 69, 255);
-
-#[derive(Debug, Clone, Deserialize)]
-struct GameConfig {
-    game: String,
-    log_location: String,
-}
+use livesplit_core::{Layout, Run, Segment, SharedTimer, Timer, TimerPhase, TimeSpan, TimingMethod};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
-#[derive(Debug, Clone)]
-struct AvailableGame {
-    display_name: String,
-    folder_name: String,
-    config: GameConfig,
-}
+/// Layout an autosplitter folder can ship, used unless `--layout` is given
+const GAME_LAYOUT_FILE: &str = "layout.lsl";
 
-fn discover_autosplitters() -> Vec<AvailableGame> {
-    let mut games = Vec::new();
-
-    // Get the executable's directory or current directory
-    let autosplitters_dir = std::env::current_exe()
-        .ok()
-        .and_then(|p| p.parent().map(|p| p.to_path_buf()))
-        .unwrap_or_else(|| PathBuf::from("."))
-        .join("autosplitters");
-
-    // Also check current working directory
-    let cwd_autosplitters = PathBuf::from("autosplitters");
-
-    for dir in [autosplitters_dir, cwd_autosplitters] {
-        if let Ok(entries) = std::fs::read_dir(&dir) {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                if path.is_dir() {
-                    let config_path = path.join("config.json");
-                    let splits_path = path.join("splits.json");
-
-                    if config_path.exists() && splits_path.exists() {
-                        if let Ok(config_content) = std::fs::read_to_string(&config_path) {
-                            if let Ok(config) = serde_json::from_str::<GameConfig>(&config_content) {
-                                let folder_name = path
-                                    .file_name()
-                                    .map(|n| n.to_string_lossy().to_string())
-                                    .unwrap_or_default();
-
-                                // Avoid duplicates
-                                if !games.iter().any(|g: &AvailableGame| g.folder_name == folder_name) {
-                                    games.push(AvailableGame {
-                                        display_name: config.game.clone(),
-                                        folder_name,
-                                        config,
-                                    });
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
-    }
+/// How often to try opening a log that couldn't be watched
+const LOG_RETRY_INTERVAL: Duration = Duration::from_secs(1);
 
-    games
-}
+/// Height of the run graph
+const GRAPH_HEIGHT: f32 = 90.0;
 
-fn get_autosplitters_base_dir() -> PathBuf {
-    // Check current working directory first
-    let cwd_autosplitters = PathBuf::from("autosplitters");
-    if cwd_autosplitters.exists() {
-        return cwd_autosplitters;
-    }
+/// Why auto-splitting isn't running, shown until it recovers
+struct Status {
+    message: String,
+    retry: Retry,
+}
 
-    // Fall back to executable directory
-    std::env::current_exe()
-        .ok()
-        .and_then(|p| p.parent().map(|p| p.to_path_buf()))
-        .unwrap_or_else(|| PathBuf::from("."))
-        .join("autosplitters")
+enum Retry {
+    Game(usize), // Loading the game failed
+    Log,         // The game loaded but its log can't be watched
 }
 
 pub struct LiveSplitApp {
-    timer: Timer,
+    timer: SharedTimer,
     splits_file: SplitsFile,
+    layout: Layout,
+    layout_path: Option<PathBuf>, // From `--layout`, for every game
     watcher: Option<LogWatcher>,
+    memory: Option<MemoryWatcher>,
+    wasm: Option<WasmAutosplitter>,
+    history: AttemptHistory,
+    recorder: Option<SessionRecorder>,
+    game_settings: GameSettings,
+    log: Option<PathBuf>,
+    log_location: Option<LogLocation>,
+    script_dir: Option<PathBuf>, // The splits file's folder, for its script and icons
+    last_log_attempt: Option<Instant>,
+    status: Option<Status>,
+    show_debug: bool,
+    show_stats: bool,
+    practice: Option<Practice>,
+    practice_range: (usize, usize), // Splits picked for the next practice
+    preferences: Preferences,
+    themes: Vec<Theme>,
+    theme_index: usize,
+    appearance_applied: bool, // Layout colors, fonts and split window match the preferences
+    icons: Option<Vec<Option<egui::TextureHandle>>>, // Per split, loaded on the next frame when None
     available_games: Vec<AvailableGame>,
+    broken_games: Vec<BrokenGame>, // Listed with why they didn't load
     selected_game_index: Option<usize>,
     pending_game_change: Option<usize>,
 }
@@ -107,38 +72,29 @@ impl LiveSplitApp {
     pub fn new(
         splits_path: Option<PathBuf>,
         watch_path: Option<PathBuf>,
+        autosplitter_path: Option<PathBuf>,
+        record_path: Option<PathBuf>,
+        layout_path: Option<PathBuf>,
     ) -> Result<Self, Box<dyn std::error::Error>> {
-        let available_games = discover_autosplitters();
+        let autosplitters = discover_autosplitters();
 
-        let (splits_file, watcher, selected_game_index) = if splits_path.is_some() || watch_path.is_some() {
-            // Use provided paths
-            let splits_file = match splits_path {
-                Some(ref path) => SplitsFile::load(path)?,
-                None => SplitsFile::default_run(),
-            };
+        // Use provided paths, or the default run with no game selected
+        let splits_file = match splits_path {
+            Some(ref path) => SplitsFile::load(path)
+                .map_err(|e| format!("Bad splits file {}: {}", path.display(), e))?,
+            None => SplitsFile::default_run(),
+        };
 
-            let watcher = if let Some(ref path) = watch_path {
-                let split_triggers: Vec<Option<String>> = splits_file
-                    .splits
-                    .iter()
-                    .map(|s| s.trigger.clone())
-                    .collect();
-
-                Some(LogWatcher::new(
-                    path.clone(),
-                    splits_file.start_trigger.clone(),
-                    splits_file.reset_trigger.clone(),
-                    split_triggers,
-                )?)
-            } else {
-                None
-            };
+        let layout = choose_layout(layout_path.as_deref(), None)?;
 
-            (splits_file, watcher, None)
-        } else {
-            // Default run, no game selected
-            (SplitsFile::default_run(), None, None)
-        };
+        // Use the splits file's folder script, if any
+        let script_dir = splits_path.as_ref().and_then(|p| p.parent()).map(Path::to_path_buf);
+        let log_location = watch_path.map(|path| {
+            let path = std::env::current_dir().map(|cwd| cwd.join(&path)).unwrap_or(path);
+            LogLocation::Literal(path)
+        });
+
+        let history = AttemptHistory::new(&splits_file.game, &splits_file.category);
 
         let mut run = Run::new();
         run.set_game_name(splits_file.game.as_str());
@@ -147,52 +103,99 @@ impl LiveSplitApp {
         for split in &splits_file.splits {
             let mut segment = Segment::new(&split.name);
             if let Some(best_ms) = split.best_time_ms {
+                let best = Some(TimeSpan::from_milliseconds(best_ms as f64));
                 let time = livesplit_core::Time::new()
-                    .with_real_time(Some(TimeSpan::from_milliseconds(best_ms as f64)));
+                    .with_real_time(best)
+                    .with_game_time(best);
                 segment.set_best_segment_time(time);
             }
             run.push_segment(segment);
         }
+        history.load_run(&mut run);
+
+        let mut timer = Timer::new(run).map_err(|_| "Failed to create timer")?;
+        layout::pick_comparison(&mut timer);
+        let timer = timer.into_shared();
+
+        let wasm = match autosplitter_path {
+            Some(ref path) => Some(attach_wasm(path, &timer)?),
+            None => None,
+        };
+
+        // Read the game's memory if the config.json next to the splits file
+        // declares it
+        let memory = match script_dir {
+            Some(ref dir) => match MemoryConfig::load_for_dir(dir)? {
+                Some(config) => Some(
+                    MemoryWatcher::new(config).map_err(|e| format!("Memory autosplitter: {}", e))?,
+                ),
+                None => None,
+            },
+            None => None,
+        };
+
+        let recorder = match record_path {
+            Some(ref path) => {
+                let mut recorder = SessionRecorder::create(path)?;
+                recorder.splits(&splits_file.game, &splits_file.category);
+                Some(recorder)
+            }
+            None => None,
+        };
 
-        let timer = Timer::new(run).map_err(|_| "Failed to create timer")?;
+        let preferences = Preferences::load();
+        let themes = Theme::all();
+        let theme_index = Theme::find(&themes, preferences.theme.as_deref());
 
-        Ok(Self {
+        let mut app = Self {
             timer,
             splits_file,
-            watcher,
-            available_games,
-            selected_game_index,
+            layout,
+            layout_path,
+            watcher: None,
+            memory,
+            wasm,
+            history,
+            recorder,
+            game_settings: GameSettings::default(),
+            log: None,
+            log_location,
+            script_dir,
+            last_log_attempt: None,
+            status: None,
+            show_debug: false,
+            show_stats: false,
+            practice: None,
+            practice_range: (0, 0),
+            preferences,
+            themes,
+            theme_index,
+            appearance_applied: false,
+            icons: None,
+            available_games: autosplitters.games,
+            broken_games: autosplitters.broken,
+            selected_game_index: None,
             pending_game_change: None,
-        })
+        };
+        app.attach_log();
+
+        Ok(app)
     }
 
     fn load_game(&mut self, game_index: usize) -> Result<(), Box<dyn std::error::Error>> {
         let game = &self.available_games[game_index];
-        let base_dir = get_autosplitters_base_dir();
-        let game_dir = base_dir.join(&game.folder_name);
+        let game_dir = game.dir.clone();
 
+        let game_settings = GameSettings::load(&game.folder_name, game.config.settings.clone());
         let splits_path = game_dir.join("splits.json");
-        let splits_file = SplitsFile::load(&splits_path)?;
-
-        // Resolve log location (relative to home directory)
-        let home_dir = dirs_next::home_dir().unwrap_or_else(|| PathBuf::from("/"));
-        let log_path = home_dir.join(&game.config.log_location);
-
-        // Create watcher
-        let split_triggers: Vec<Option<String>> = splits_file
-            .splits
-            .iter()
-            .map(|s| s.trigger.clone())
-            .collect();
-
-        let watcher = LogWatcher::new(
-            log_path,
-            splits_file.start_trigger.clone(),
-            splits_file.reset_trigger.clone(),
-            split_triggers,
-        ).ok();
-
-        // Create new timer
+        let splits_file = SplitsFile::load_with_settings(&splits_path, &game_settings)
+            .map_err(|e| format!("Bad splits file {}: {}", splits_path.display(), e))?;
+
+        let wasm_path = game.config.wasm.as_ref().map(|w| game_dir.join(w));
+        let layout = choose_layout(self.layout_path.as_deref(), Some(&game_dir))?;
+
+        // Create new timer, with the game's earlier attempts
+        let history = AttemptHistory::new(&splits_file.game, &splits_file.category);
         let mut run = Run::new();
         run.set_game_name(splits_file.game.as_str());
         run.set_category_name(splits_file.category.as_str());
@@ -200,169 +203,441 @@ impl LiveSplitApp {
         for split in &splits_file.splits {
             let mut segment = Segment::new(&split.name);
             if let Some(best_ms) = split.best_time_ms {
+                let best = Some(TimeSpan::from_milliseconds(best_ms as f64));
                 let time = livesplit_core::Time::new()
-                    .with_real_time(Some(TimeSpan::from_milliseconds(best_ms as f64)));
+                    .with_real_time(best)
+                    .with_game_time(best);
                 segment.set_best_segment_time(time);
             }
             run.push_segment(segment);
         }
+        history.load_run(&mut run);
+
+        let mut timer = Timer::new(run).map_err(|_| "Failed to create timer")?;
+        layout::pick_comparison(&mut timer);
+        let timer = timer.into_shared();
+
+        // Everything that can fail comes first, so a game that doesn't load
+        // leaves the current one running
+        let wasm = match wasm_path {
+            Some(ref path) => Some(
+                attach_wasm(path, &timer)
+                    .map_err(|e| format!("Could not load {}: {}", path.display(), e))?,
+            ),
+            None => None,
+        };
+        let memory = match game.config.memory {
+            Some(ref config) => Some(
+                MemoryWatcher::new(config.clone())
+                    .map_err(|e| format!("Memory autosplitter: {}", e))?,
+            ),
+            None => None,
+        };
 
-        let timer = Timer::new(run).map_err(|_| "Failed to create timer")?;
-
+        // Stop the previous game's runtime before dropping the timer it drives
+        self.wasm = wasm;
         self.timer = timer;
+        self.memory = memory;
+        self.history = history;
+        if let Some(ref mut recorder) = self.recorder {
+            recorder.splits(&splits_file.game, &splits_file.category);
+        }
+        self.game_settings = game_settings;
+        self.log_location = game.config.log_location.clone();
+        self.script_dir = Some(game_dir);
         self.splits_file = splits_file;
-        self.watcher = watcher;
+        self.layout = layout;
+        self.appearance_applied = false;
+        self.icons = None;
+        self.practice = None;
+        self.practice_range = (0, 0);
         self.selected_game_index = Some(game_index);
+        self.status = None;
+        self.attach_log();
 
         Ok(())
     }
 
+    /// (Re)create the log watcher from the log location. On failure the
+    /// reason is shown and `update` keeps retrying until the log appears.
+    fn attach_log(&mut self) {
+        self.watcher = None;
+        self.log = None;
+        let Some(ref location) = self.log_location else {
+            return;
+        };
+
+        self.last_log_attempt = Some(Instant::now());
+        self.log = location.resolve();
+
+        let result = match self.log {
+            Some(ref path) => LogWatcher::for_splits(path.clone(), &self.splits_file, self.script_dir.as_deref())
+                .map(LogWatcher::with_debug)
+                .map(|w| if self.recorder.is_some() { w.with_recording() } else { w }),
+            None => Err(format!("No log location for {}", std::env::consts::OS).into()),
+        };
+
+        match result {
+            Ok(mut watcher) => {
+                // Attached mid-run: continue from the timer's split
+                let index = self.split_index(&self.timer.read().unwrap());
+                watcher.set_split_index(index);
+                self.watcher = Some(watcher);
+                if matches!(self.status, Some(Status { retry: Retry::Log, .. })) {
+                    self.status = None;
+                }
+            }
+            Err(e) => {
+                self.status = Some(Status {
+                    message: e.to_string(),
+                    retry: Retry::Log,
+                });
+            }
+        }
+    }
+
     fn poll_watcher(&mut self) {
+        let mut events = Vec::new();
         if let Some(ref mut w) = self.watcher {
-            for event in w.poll() {
-                match event {
-                    WatchEvent::Start => {
-                        if self.timer.current_phase() == TimerPhase::NotRunning {
-                            self.timer.start();
-                        }
+            events.extend(w.poll());
+            if let Some(ref mut recorder) = self.recorder {
+                for line in w.take_recorded() {
+                    recorder.log_line(&line);
+                }
+            }
+        }
+        if let Some(ref mut m) = self.memory {
+            events.extend(m.poll());
+        }
+        let split_index = self.split_index(&self.timer.read().unwrap());
+        if let Some(ref mut wasm) = self.wasm {
+            events.extend(wasm.poll(split_index));
+        }
+
+        let shared_timer = self.timer.clone();
+        let mut timer = shared_timer.write().unwrap();
+        for event in events {
+            match event {
+                WatchEvent::Start => {
+                    // Practice from a later split starts on the split before it instead
+                    let starts = self.practice.as_ref().is_none_or(|p| p.first == 0);
+                    if starts && timer.current_phase() == TimerPhase::NotRunning {
+                        timer.start();
                     }
-                    WatchEvent::Split(_) => {
-                        if self.timer.current_phase() == TimerPhase::Running {
-                            self.timer.split();
+                }
+                WatchEvent::Split(index) => {
+                    let starts_practice = self.practice.as_ref().is_some_and(|p| p.starts_on_split(index));
+                    if starts_practice && timer.current_phase() == TimerPhase::NotRunning {
+                        timer.start();
+                    } else if timer.current_phase() == TimerPhase::Running {
+                        timer.split();
+                        if timer.current_phase() == TimerPhase::Ended {
+                            self.record_outcome(&timer, Outcome::Finished, None);
                         }
                     }
-                    WatchEvent::Reset => {
-                        self.timer.reset(true);
-                        w.reset_split_index();
+                }
+                WatchEvent::Reset => {
+                    self.record_outcome(&timer, Outcome::Reset, None);
+                    self.reset(&mut timer);
+                }
+                WatchEvent::Pause => {
+                    if timer.current_phase() == TimerPhase::Running {
+                        timer.pause();
+                    }
+                }
+                WatchEvent::Resume => {
+                    if timer.current_phase() == TimerPhase::Paused {
+                        timer.resume();
+                    }
+                }
+                WatchEvent::Skip(_) => {
+                    if timer.current_phase() == TimerPhase::Running {
+                        timer.skip_split();
+                    }
+                }
+                WatchEvent::Ended(ending) => {
+                    let policy = self.splits_file.endings.policy(ending);
+                    self.record_outcome(&timer, ending.into(), Some(policy));
+                    if policy.apply(&mut timer) {
+                        self.reset(&mut timer);
                     }
                 }
             }
         }
     }
 
-    fn format_time(time_span: Option<TimeSpan>) -> String {
-        match time_span {
-            Some(ts) => {
-                let total_secs = ts.total_seconds();
-                let negative = total_secs < 0.0;
-                let total_secs = total_secs.abs();
+    /// Reset the timer and move the watchers back to where a run starts.
+    /// Practice attempts are kept as segment history instead.
+    fn reset(&mut self, timer: &mut Timer) {
+        match self.practice {
+            Some(ref mut practice) => {
+                practice.record(timer);
+                timer.reset(false);
+                self.history.save_run(practice.full_run());
+            }
+            None => {
+                timer.reset(true);
+                self.history.save_run(timer.run());
+                layout::pick_comparison(timer);
+            }
+        }
+        self.sync_split_index(timer);
+    }
 
-                let hours = (total_secs / 3600.0) as u32;
-                let mins = ((total_secs % 3600.0) / 60.0) as u32;
-                let secs = (total_secs % 60.0) as u32;
-                let ms = ((total_secs * 1000.0) as u32) % 1000;
+    /// The split of the full run the watchers should be at
+    fn split_index(&self, timer: &Timer) -> usize {
+        match self.practice {
+            Some(ref practice) => practice.split_index(timer),
+            None => timer.current_split_index().unwrap_or(0),
+        }
+    }
 
-                let sign = if negative { "-" } else { "" };
+    fn sync_split_index(&mut self, timer: &Timer) {
+        let index = self.split_index(timer);
+        if let Some(ref mut w) = self.watcher {
+            w.set_split_index(index);
+        }
+        if let Some(ref mut m) = self.memory {
+            m.set_split_index(index);
+        }
+    }
 
-                if hours > 0 {
-                    format!("{}{}:{:02}:{:02}.{:02}", sign, hours, mins, secs, ms / 10)
-                } else {
-                    format!("{}{}:{:02}.{:02}", sign, mins, secs, ms / 10)
-                }
-            }
-            None => "-".to_string(),
+    /// Practice attempts stay out of the attempt history
+    fn record_outcome(&mut self, timer: &Timer, outcome: Outcome, policy: Option<Policy>) {
+        if self.practice.is_none() {
+            self.history.record(timer, outcome, policy);
         }
     }
 
-    fn format_time_ms(time_span: Option<TimeSpan>) -> String {
-        match time_span {
-            Some(ts) => {
-                let total_secs = ts.total_seconds();
-                let negative = total_secs < 0.0;
-                let total_secs = total_secs.abs();
+    fn set_theme(&mut self, index: usize) {
+        self.theme_index = index;
+        self.preferences.theme = Some(self.themes[index].name.clone());
+        self.preferences.save();
+        self.appearance_applied = false;
+    }
 
-                let hours = (total_secs / 3600.0) as u32;
-                let mins = ((total_secs % 3600.0) / 60.0) as u32;
-                let secs = (total_secs % 60.0) as u32;
-                let ms = ((total_secs * 1000.0) as u32) % 1000;
+    /// Color the layout, load the font of the current theme and size the
+    /// splits list. Layouts keep their own split count unless one is set.
+    fn apply_appearance(&mut self, ctx: &egui::Context) {
+        let theme = &self.themes[self.theme_index];
+        layout::apply_theme(&mut self.layout, theme);
+        ctx.set_fonts(font_definitions(theme));
+        if let Some(visible) = self.preferences.visible_splits {
+            layout::apply_split_window(&mut self.layout, visible, self.preferences.split_preview);
+        }
+        self.appearance_applied = true;
+    }
 
-                let sign = if negative { "-" } else { "" };
+    /// Make the window match the window preferences. Transparency only
+    /// needs the background left out, as the window is made transparent.
+    fn apply_window(&self, ctx: &egui::Context) {
+        let window = &self.preferences.window;
+        let level = if window.always_on_top {
+            egui::WindowLevel::AlwaysOnTop
+        } else {
+            egui::WindowLevel::Normal
+        };
+        ctx.send_viewport_cmd(egui::ViewportCommand::WindowLevel(level));
+        ctx.send_viewport_cmd(egui::ViewportCommand::Decorations(!window.borderless));
+        ctx.send_viewport_cmd(egui::ViewportCommand::MousePassthrough(window.click_through));
+    }
 
-                if hours > 0 {
-                    format!("{}{}:{:02}:{:02}.{:03}", sign, hours, mins, secs, ms)
-                } else if mins > 0 {
-                    format!("{}{}:{:02}.{:03}", sign, mins, secs, ms)
-                } else {
-                    format!("{}{}.{:03}", sign, secs, ms)
-                }
-            }
-            None => "0.000".to_string(),
+    fn record_action(&mut self, action: ManualAction) {
+        if let Some(ref mut recorder) = self.recorder {
+            recorder.action(action);
         }
     }
 }
 
 impl eframe::App for LiveSplitApp {
+    /// Fully transparent; the central panel paints the background unless
+    /// it is turned off
+    fn clear_color(&self, _visuals: &egui::Visuals) -> [f32; 4] {
+        [0.0; 4]
+    }
+
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         // Handle pending game change
         if let Some(game_index) = self.pending_game_change.take() {
-            let _ = self.load_game(game_index);
+            if let Err(e) = self.load_game(game_index) {
+                self.status = Some(Status {
+                    message: e.to_string(),
+                    retry: Retry::Game(game_index),
+                });
+            }
+        }
+
+        if !self.appearance_applied {
+            self.apply_appearance(ctx);
+        }
+        if self.icons.is_none() {
+            let dir = self.script_dir.as_deref().unwrap_or(Path::new("."));
+            self.icons = Some(load_icons(ctx, &self.splits_file, dir));
+        }
+
+        // Re-attach once the log can be opened
+        if self.watcher.is_none()
+            && self.log_location.is_some()
+            && self.last_log_attempt.is_none_or(|t| t.elapsed() >= LOG_RETRY_INTERVAL)
+        {
+            self.attach_log();
         }
 
         self.poll_watcher();
 
+        // Remember where the window was for the next start
+        let (closing, outer, inner) =
+            ctx.input(|i| (i.viewport().close_requested(), i.viewport().outer_rect, i.viewport().inner_rect));
+        if closing {
+            let window = &mut self.preferences.window;
+            window.position = outer.map(|r| [r.min.x, r.min.y]).or(window.position);
+            window.size = inner.map(|r| [r.width(), r.height()]).or(window.size);
+            self.preferences.save();
+
+            // A run still going, or finished but not reset, goes into the history
+            let shared_timer = self.timer.clone();
+            let mut timer = shared_timer.write().unwrap();
+            if timer.current_phase() != TimerPhase::NotRunning {
+                self.record_outcome(&timer, Outcome::Closed, None);
+                self.reset(&mut timer);
+            }
+        }
+
         // Request continuous repaints for timer updates
         ctx.request_repaint();
 
+        let shared_timer = self.timer.clone();
+        let mut timer = shared_timer.write().unwrap();
+
         // Handle keyboard input
+        let mut window_changed = false;
         ctx.input(|i| {
             if i.key_pressed(egui::Key::Space) {
-                match self.timer.current_phase() {
-                    TimerPhase::NotRunning => self.timer.start(),
-                    TimerPhase::Running => self.timer.split(),
+                match timer.current_phase() {
+                    TimerPhase::NotRunning => {
+                        timer.start();
+                        self.record_action(ManualAction::Start);
+                        self.sync_split_index(&timer);
+                    }
+                    TimerPhase::Running => {
+                        timer.split();
+                        self.record_action(ManualAction::Split);
+                        if timer.current_phase() == TimerPhase::Ended {
+                            self.record_outcome(&timer, Outcome::Finished, None);
+                        }
+                    }
                     TimerPhase::Ended => {}
-                    TimerPhase::Paused => self.timer.resume(),
+                    TimerPhase::Paused => {
+                        timer.resume();
+                        self.record_action(ManualAction::Resume);
+                    }
                 }
             }
             if i.key_pressed(egui::Key::R) {
-                self.timer.reset(true);
-                if let Some(ref mut w) = self.watcher {
-                    w.reset_split_index();
-                }
+                self.record_outcome(&timer, Outcome::ManualReset, None);
+                self.reset(&mut timer);
+                self.record_action(ManualAction::Reset);
             }
             if i.key_pressed(egui::Key::P) {
-                match self.timer.current_phase() {
-                    TimerPhase::Running => self.timer.pause(),
-                    TimerPhase::Paused => self.timer.resume(),
+                match timer.current_phase() {
+                    TimerPhase::Running => {
+                        timer.pause();
+                        self.record_action(ManualAction::Pause);
+                    }
+                    TimerPhase::Paused => {
+                        timer.resume();
+                        self.record_action(ManualAction::Resume);
+                    }
                     _ => {}
                 }
             }
             if i.key_pressed(egui::Key::U) {
-                self.timer.undo_split();
-                if let Some(ref mut w) = self.watcher {
-                    let idx = self.timer.current_split_index().unwrap_or(0);
-                    w.set_split_index(idx);
-                }
+                timer.undo_split();
+                self.record_action(ManualAction::Undo);
+                self.sync_split_index(&timer);
             }
             if i.key_pressed(egui::Key::S) {
-                self.timer.skip_split();
-                if let Some(ref mut w) = self.watcher {
-                    let idx = self.timer.current_split_index().unwrap_or(0);
-                    w.set_split_index(idx);
-                }
+                timer.skip_split();
+                self.record_action(ManualAction::Skip);
+                self.sync_split_index(&timer);
+            }
+            if i.key_pressed(egui::Key::D) {
+                self.show_debug = !self.show_debug;
+            }
+            if i.key_pressed(egui::Key::I) {
+                self.show_stats = !self.show_stats;
+            }
+            if i.key_pressed(egui::Key::T) {
+                self.set_theme((self.theme_index + 1) % self.themes.len());
+            }
+            // The way back from click-through, which the settings can't be clicked in
+            if i.key_pressed(egui::Key::C) {
+                self.preferences.window.click_through = !self.preferences.window.click_through;
+                window_changed = true;
             }
         });
+        if window_changed {
+            self.preferences.save();
+            self.apply_window(ctx);
+        }
 
-        let snapshot = self.timer.snapshot();
-        let current_time = snapshot.current_time().real_time;
-        let phase = self.timer.current_phase();
-        let current_split_idx = self.timer.current_split_index().unwrap_or(0);
+        let layout_state = self.layout.state(&timer.snapshot());
+        let graph = self
+            .preferences
+            .show_graph
+            .then(|| graph_lines(&timer, self.preferences.graph_attempts));
+        let split_count = timer.run().len();
+        let stats = self.show_stats.then(|| {
+            // The full run while practicing, which has the practice history
+            let run = self.practice.as_ref().map_or(timer.run(), Practice::full_run);
+            let method = timer.current_timing_method();
+            let golds: Vec<_> = run
+                .segments()
+                .iter()
+                .map(|s| s.best_segment_time()[method].map(|t| t.total_seconds()))
+                .collect();
+            let names: Vec<String> = run
+                .segments()
+                .iter()
+                .map(|s| display_name(s.name()).into_owned())
+                .collect();
+            let segment_times: Vec<Vec<f64>> = run
+                .segments()
+                .iter()
+                .map(|s| {
+                    s.segment_history()
+                        .iter()
+                        .filter_map(|&(_, time)| time[method])
+                        .map(|t| t.total_seconds())
+                        .collect()
+                })
+                .collect();
+            let attempts: Vec<usize> = history_segment_times(run, method).iter().map(Vec::len).collect();
+            (names, stats::segment_stats(&segment_times, &golds, &attempts), attempts.len())
+        });
+        let theme = self.themes[self.theme_index].clone();
+        let background = if self.preferences.window.transparent {
+            egui::Color32::TRANSPARENT
+        } else {
+            color(theme.background)
+        };
 
         egui::CentralPanel::default()
-            .frame(egui::Frame::none().fill(DARK_BG))
+            .frame(egui::Frame::none().fill(background))
             .show(ctx, |ui| {
-                ui.style_mut().visuals.override_text_color = Some(TEXT_WHITE);
+                ui.style_mut().visuals.override_text_color = Some(color(theme.text));
 
                 // Game selector dropdown
-                if !self.available_games.is_empty() {
+                if !self.available_games.is_empty() || !self.broken_games.is_empty() {
                     egui::Frame::none()
-                        .fill(HEADER_BG)
+                        .fill(color(theme.header_background))
                         .inner_margin(egui::Margin::symmetric(12.0, 8.0))
                         .show(ui, |ui| {
                             ui.horizontal(|ui| {
                                 ui.label(
                                     egui::RichText::new("Game:")
                                         .size(12.0)
-                                        .color(TEXT_GRAY),
+                                        .color(color(theme.text_dim)),
                                 );
 
                                 let current_selection = self
@@ -376,10 +651,24 @@ impl eframe::App for LiveSplitApp {
                                     .show_ui(ui, |ui| {
                                         for (i, game) in self.available_games.iter().enumerate() {
                                             let is_selected = self.selected_game_index == Some(i);
-                                            if ui.selectable_label(is_selected, &game.display_name).clicked() {
+                                            let mut label = ui.selectable_label(is_selected, &game.display_name);
+                                            if let Some(ref manifest) = game.manifest {
+                                                let author = manifest
+                                                    .author
+                                                    .as_ref()
+                                                    .map(|a| format!(" by {}", a))
+                                                    .unwrap_or_default();
+                                                label = label.on_hover_text(format!("v{}{}", manifest.version, author));
+                                            }
+                                            if label.clicked() {
                                                 self.pending_game_change = Some(i);
                                             }
                                         }
+                                        for broken in &self.broken_games {
+                                            let name = broken.dir.file_name().unwrap_or_default().to_string_lossy();
+                                            ui.add_enabled(false, egui::SelectableLabel::new(false, format!("{} (broken)", name)))
+                                                .on_disabled_hover_text(&broken.error);
+                                        }
                                     });
                             });
                         });
@@ -387,212 +676,480 @@ impl eframe::App for LiveSplitApp {
                     ui.add_space(2.0);
                 }
 
-                // Header - Game and Category
-                egui::Frame::none()
-                    .fill(HEADER_BG)
-                    .inner_margin(egui::Margin::symmetric(12.0, 8.0))
-                    .show(ui, |ui| {
-                        ui.vertical_centered(|ui| {
-                            ui.label(
-                                egui::RichText::new(&self.splits_file.game)
-                                    .size(18.0)
-                                    .strong()
-                                    .color(TEXT_WHITE),
-                            );
-                            ui.label(
-                                egui::RichText::new(&self.splits_file.category)
-                                    .size(14.0)
-                                    .color(TEXT_GRAY),
-                            );
-                        });
-                    });
-
-                ui.add_space(2.0);
-
-                // Splits list
-                let run = self.timer.run();
-                let mut prev_split_time: Option<TimeSpan> = None;
-                for (i, split) in self.splits_file.splits.iter().enumerate() {
-                    let segment = run.segment(i);
-                    let split_time = segment.split_time().real_time;
-                    let best_segment = segment.best_segment_time().real_time;
-
-                    // Calculate current segment time (time for this segment only)
-                    let current_segment_time = if let Some(split) = split_time {
-                        if let Some(prev) = prev_split_time {
-                            Some(TimeSpan::from_seconds(split.total_seconds() - prev.total_seconds()))
-                        } else {
-                            Some(split)
-                        }
-                    } else {
-                        None
-                    };
-
-                    let is_current = i == current_split_idx && phase == TimerPhase::Running;
-                    let is_completed = i < current_split_idx;
+                // Scrolling over the layout previews the splits out of view
+                let mut icons = self.icons.as_deref().unwrap_or_default();
+                if let Some(ref practice) = self.practice {
+                    icons = icons.get(practice.first..=practice.last).unwrap_or_default();
+                }
+                let layout_area = ui.scope(|ui| layout::show(ui, &layout_state, &theme, icons)).response;
+                if layout_area.contains_pointer() {
+                    let scrolled = ui.input(|i| i.raw_scroll_delta.y);
+                    if scrolled > 0.0 {
+                        self.layout.scroll_up();
+                    } else if scrolled < 0.0 {
+                        self.layout.scroll_down();
+                    }
+                }
+                // Without a title bar the window is moved by dragging the layout
+                if self.preferences.window.borderless {
+                    let drag = ui.interact(layout_area.rect, ui.id().with("move_window"), egui::Sense::drag());
+                    if drag.drag_started() {
+                        ctx.send_viewport_cmd(egui::ViewportCommand::StartDrag);
+                    }
+                }
 
-                    let bg_color = if is_current {
-                        SPLIT_CURRENT_BG
-                    } else {
-                        SPLIT_BG
-                    };
+                if let Some(ref lines) = graph {
+                    show_graph(ui, lines, split_count, &theme);
+                    ui.add_space(4.0);
+                }
 
+                // Autosplitter info rows (e.g. seed, character)
+                let info_rows = self
+                    .watcher
+                    .as_ref()
+                    .map(|w| w.info_rows())
+                    .unwrap_or_default();
+                if !info_rows.is_empty() {
                     egui::Frame::none()
-                        .fill(bg_color)
+                        .fill(color(theme.split_background))
                         .inner_margin(egui::Margin::symmetric(10.0, 6.0))
                         .show(ui, |ui| {
-                            ui.horizontal(|ui| {
-                                // Split name
-                                let name_color = if is_completed {
-                                    TEXT_GRAY
-                                } else if is_current {
-                                    TEXT_WHITE
-                                } else {
-                                    TEXT_GRAY
-                                };
-
-                                ui.with_layout(egui::Layout::left_to_right(egui::Align::Center), |ui| {
+                            for (label, value) in &info_rows {
+                                ui.horizontal(|ui| {
                                     ui.label(
-                                        egui::RichText::new(&split.name)
-                                            .size(14.0)
-                                            .color(name_color),
+                                        egui::RichText::new(format!("{}:", label))
+                                            .size(12.0)
+                                            .color(color(theme.text_dim)),
                                     );
-                                });
-
-                                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                                    // Split time (right aligned)
-                                    if is_completed {
-                                        let time_str = Self::format_time(split_time);
+                                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                                         ui.label(
-                                            egui::RichText::new(time_str)
-                                                .size(14.0)
-                                                .color(TEXT_WHITE)
+                                            egui::RichText::new(value)
+                                                .size(12.0)
+                                                .color(color(theme.text))
                                                 .monospace(),
                                         );
-
-                                        // Delta: compare current segment time vs best segment time
-                                        if let (Some(current_seg), Some(best_seg)) = (current_segment_time, best_segment) {
-                                            let delta = current_seg.total_seconds() - best_seg.total_seconds();
-                                            let delta_color = if delta < 0.0 {
-                                                TIME_GREEN
-                                            } else if delta < 1.0 {
-                                                TIME_GOLD
-                                            } else {
-                                                TIME_RED
-                                            };
-                                            let delta_str = if delta >= 0.0 {
-                                                format!("+{:.2}", delta)
-                                            } else {
-                                                format!("{:.2}", delta)
-                                            };
-                                            ui.add_space(10.0);
-                                            ui.label(
-                                                egui::RichText::new(delta_str)
-                                                    .size(12.0)
-                                                    .color(delta_color)
-                                                    .monospace(),
-                                            );
-                                        }
-                                    } else if !is_current {
-                                        // Show best time for upcoming splits
-                                        if let Some(_best) = best_segment {
-                                            let time_str = Self::format_time(best_segment);
-                                            ui.label(
-                                                egui::RichText::new(time_str)
-                                                    .size(14.0)
-                                                    .color(TEXT_GRAY)
-                                                    .monospace(),
-                                            );
-                                        } else {
-                                            ui.label(
-                                                egui::RichText::new("-")
-                                                    .size(14.0)
-                                                    .color(TEXT_GRAY)
-                                                    .monospace(),
-                                            );
-                                        }
-                                    }
+                                    });
                                 });
-                            });
+                            }
                         });
 
-                    // Track previous split time for segment calculation
-                    if is_completed {
-                        prev_split_time = split_time;
-                    }
-
-                    ui.add_space(1.0);
+                    ui.add_space(2.0);
                 }
 
-                ui.add_space(4.0);
-
-                // Main timer display
-                egui::Frame::none()
-                    .fill(HEADER_BG)
-                    .inner_margin(egui::Margin::symmetric(12.0, 16.0))
-                    .show(ui, |ui| {
-                        ui.vertical_centered(|ui| {
-                            let time_color = match phase {
-                                TimerPhase::NotRunning => TEXT_WHITE,
-                                TimerPhase::Running => TIME_GREEN,
-                                TimerPhase::Paused => TIME_GOLD,
-                                TimerPhase::Ended => TIME_BLUE,
-                            };
-
-                            let time_str = Self::format_time_ms(current_time);
-                            ui.label(
-                                egui::RichText::new(time_str)
-                                    .size(48.0)
-                                    .strong()
-                                    .color(time_color)
-                                    .monospace(),
-                            );
-                        });
-                    });
-
-                ui.add_space(8.0);
-
                 // Controls hint
                 egui::Frame::none()
-                    .fill(SPLIT_BG)
+                    .fill(color(theme.split_background))
                     .inner_margin(egui::Margin::symmetric(10.0, 8.0))
                     .show(ui, |ui| {
                         ui.vertical_centered(|ui| {
                             ui.label(
                                 egui::RichText::new("Space: Start/Split | P: Pause | R: Reset")
                                     .size(11.0)
-                                    .color(TEXT_GRAY),
+                                    .color(color(theme.text_dim)),
                             );
                             ui.label(
-                                egui::RichText::new("U: Undo | S: Skip | Esc: Quit")
+                                egui::RichText::new("U: Undo | S: Skip | D: Debug | T: Theme | Esc: Quit")
                                     .size(11.0)
-                                    .color(TEXT_GRAY),
+                                    .color(color(theme.text_dim)),
                             );
-                            if self.watcher.is_some() {
+                            let attached = self.memory.as_ref().is_some_and(MemoryWatcher::is_attached);
+                            let wasm_error = self.wasm.as_ref().and_then(WasmAutosplitter::error);
+                            let wasm_running = self.wasm.is_some() && wasm_error.is_none();
+                            if self.watcher.is_some() || attached || wasm_running {
                                 ui.add_space(4.0);
                                 ui.label(
                                     egui::RichText::new("Auto-split active")
                                         .size(11.0)
-                                        .color(ACCENT_COLOR),
+                                        .color(color(theme.accent)),
+                                );
+                            }
+                            if let Some(m) = self.memory.as_ref().filter(|m| !m.is_attached()) {
+                                ui.label(
+                                    egui::RichText::new(format!("Waiting for {} to start", m.process_name()))
+                                        .size(10.0)
+                                        .color(color(theme.text_dim)),
+                                );
+                            }
+                            if let Some(ref path) = self.log {
+                                if self.watcher.is_some() {
+                                    ui.label(
+                                        egui::RichText::new(format!("Log: {}", path.display()))
+                                            .size(10.0)
+                                            .color(color(theme.text_dim)),
+                                    );
+                                }
+                            }
+                            if let Some(ref status) = self.status {
+                                ui.add_space(4.0);
+                                ui.label(
+                                    egui::RichText::new(&status.message)
+                                        .size(11.0)
+                                        .color(color(theme.error)),
                                 );
+                                if ui.small_button("Retry").clicked() {
+                                    match status.retry {
+                                        Retry::Game(index) => self.pending_game_change = Some(index),
+                                        Retry::Log => self.last_log_attempt = None,
+                                    }
+                                }
+                            }
+                            if let Some(error) = self.watcher.as_ref().and_then(LogWatcher::script_error) {
+                                ui.add_space(4.0);
+                                ui.label(egui::RichText::new(error).size(11.0).color(color(theme.error)));
+                            }
+                            if let Some(error) = wasm_error {
+                                ui.add_space(4.0);
+                                ui.label(egui::RichText::new(error).size(11.0).color(color(theme.error)));
                             }
                         });
                     });
+
+                // WebAssembly autosplitter settings
+                if let Some(ref wasm) = self.wasm {
+                    let settings = wasm.settings();
+                    if !settings.is_empty() {
+                        ui.add_space(4.0);
+                        egui::CollapsingHeader::new(
+                            egui::RichText::new("Autosplitter settings")
+                                .size(12.0)
+                                .color(color(theme.text_dim)),
+                        )
+                        .show(ui, |ui| {
+                            for setting in settings {
+                                let mut value = setting.value;
+                                if ui.checkbox(&mut value, &setting.description).changed() {
+                                    wasm.set_setting(&setting.key, value);
+                                }
+                            }
+                        });
+                    }
+                }
+
+                // Settings declared by the game's config.json. They decide
+                // which splits are in the run, so they can't change mid-run.
+                if !self.game_settings.is_empty() {
+                    ui.add_space(4.0);
+                    egui::CollapsingHeader::new(
+                        egui::RichText::new("Game settings")
+                            .size(12.0)
+                            .color(color(theme.text_dim)),
+                    )
+                    .show(ui, |ui| {
+                        let idle = timer.current_phase() == TimerPhase::NotRunning;
+                        let mut changed = None;
+
+                        ui.add_enabled_ui(idle, |ui| {
+                            for def in self.game_settings.definitions() {
+                                let current = self.game_settings.get(&def.key);
+                                match (&def.kind, current) {
+                                    (SettingKind::Bool { .. }, Some(SettingValue::Bool(on))) => {
+                                        let mut value = *on;
+                                        if ui.checkbox(&mut value, &def.label).changed() {
+                                            changed = Some((def.key.clone(), SettingValue::Bool(value)));
+                                        }
+                                    }
+                                    (SettingKind::Choice { options, .. }, Some(SettingValue::Choice(selected))) => {
+                                        let mut value = selected.clone();
+                                        egui::ComboBox::from_label(&def.label)
+                                            .selected_text(value.as_str())
+                                            .show_ui(ui, |ui| {
+                                                for option in options {
+                                                    ui.selectable_value(&mut value, option.clone(), option);
+                                                }
+                                            });
+                                        if &value != selected {
+                                            changed = Some((def.key.clone(), SettingValue::Choice(value)));
+                                        }
+                                    }
+                                    _ => {}
+                                }
+                            }
+                        });
+
+                        if let Some((key, value)) = changed {
+                            // Reload the game so the splits and watcher pick it up
+                            if self.game_settings.set(&key, value) {
+                                self.pending_game_change = self.selected_game_index;
+                            }
+                        }
+                    });
+                }
+
+                // Practice: time a range of splits on its own, started by the
+                // trigger of the split before it
+                ui.add_space(4.0);
+                egui::CollapsingHeader::new(
+                    egui::RichText::new("Practice")
+                        .size(12.0)
+                        .color(color(theme.text_dim)),
+                )
+                .show(ui, |ui| {
+                    let names: Vec<String> =
+                        self.splits_file.splits.iter().map(|s| s.display_name().to_string()).collect();
+                    let idle = timer.current_phase() == TimerPhase::NotRunning;
+
+                    if let Some(ref practice) = self.practice {
+                        ui.label(format!("Practicing {} to {}", names[practice.first], names[practice.last]));
+                        if ui.add_enabled(idle, egui::Button::new("Back to full runs")).clicked() {
+                            if let Some(practice) = self.practice.take() {
+                                practice.stop(&mut timer);
+                            }
+                            self.sync_split_index(&timer);
+                        }
+                        return;
+                    }
+
+                    let (mut first, mut last) = self.practice_range;
+                    for (label, index) in [("From", &mut first), ("To", &mut last)] {
+                        egui::ComboBox::from_label(label)
+                            .selected_text(names.get(*index).map_or("", String::as_str))
+                            .show_ui(ui, |ui| {
+                                for (i, name) in names.iter().enumerate() {
+                                    ui.selectable_value(index, i, name);
+                                }
+                            });
+                    }
+                    self.practice_range = (first, last.max(first));
+
+                    if ui.add_enabled(idle, egui::Button::new("Practice")).clicked() {
+                        let (first, last) = self.practice_range;
+                        // The range always names existing splits
+                        if let Ok(practice) = Practice::start(&mut timer, first, last) {
+                            self.practice = Some(practice);
+                            self.sync_split_index(&timer);
+                        }
+                    }
+                });
+
+                ui.add_space(4.0);
+                egui::CollapsingHeader::new(
+                    egui::RichText::new("Appearance")
+                        .size(12.0)
+                        .color(color(theme.text_dim)),
+                )
+                .show(ui, |ui| {
+                    let mut selected = self.theme_index;
+                    egui::ComboBox::from_label("Theme")
+                        .selected_text(&theme.name)
+                        .show_ui(ui, |ui| {
+                            for (i, t) in self.themes.iter().enumerate() {
+                                ui.selectable_value(&mut selected, i, &t.name);
+                            }
+                        });
+                    if selected != self.theme_index {
+                        self.set_theme(selected);
+                    }
+
+                    // Unset goes back to the layout's own count
+                    let mut limit = self.preferences.visible_splits.is_some();
+                    let mut visible = self.preferences.visible_splits.unwrap_or(10);
+                    let mut preview = self.preferences.split_preview;
+                    let mut changed = ui.checkbox(&mut limit, "Limit visible splits").changed();
+                    ui.add_enabled_ui(limit, |ui| {
+                        ui.horizontal(|ui| {
+                            changed |= ui.add(egui::DragValue::new(&mut visible).range(1..=100)).changed();
+                            ui.label("splits shown");
+                        });
+                        ui.horizontal(|ui| {
+                            changed |= ui.add(egui::DragValue::new(&mut preview).range(0..=10)).changed();
+                            ui.label("upcoming splits kept in view");
+                        });
+                    });
+                    if changed {
+                        if !limit {
+                            let game_dir = self.selected_game_index.map(|i| self.available_games[i].dir.clone());
+                            if let Ok(layout) = choose_layout(self.layout_path.as_deref(), game_dir.as_deref()) {
+                                self.layout = layout;
+                            }
+                        }
+                        self.preferences.visible_splits = limit.then_some(visible);
+                        self.preferences.split_preview = preview;
+                        self.preferences.save();
+                        self.appearance_applied = false;
+                    }
+
+                    let mut graph_shown = self.preferences.show_graph;
+                    let mut attempts = self.preferences.graph_attempts;
+                    let mut changed = ui.checkbox(&mut graph_shown, "Show run graph").changed();
+                    ui.add_enabled_ui(graph_shown, |ui| {
+                        ui.horizontal(|ui| {
+                            changed |= ui.add(egui::DragValue::new(&mut attempts).range(0..=20)).changed();
+                            ui.label("earlier attempts in the graph");
+                        });
+                    });
+                    if changed {
+                        self.preferences.show_graph = graph_shown;
+                        self.preferences.graph_attempts = attempts;
+                        self.preferences.save();
+                    }
+
+                    ui.separator();
+                    let mut window = self.preferences.window.clone();
+                    ui.checkbox(&mut window.always_on_top, "Always on top");
+                    ui.checkbox(&mut window.borderless, "Borderless (drag the splits to move)");
+                    ui.checkbox(&mut window.transparent, "Transparent background");
+                    ui.checkbox(&mut window.click_through, "Click-through (C to turn off)");
+                    if window != self.preferences.window {
+                        self.preferences.window = window;
+                        self.preferences.save();
+                        self.apply_window(ctx);
+                    }
+                });
+            });
+
+        // Debug console: the log lines the watcher saw and what they matched
+        let mut show_debug = self.show_debug;
+        egui::Window::new("Debug console")
+            .open(&mut show_debug)
+            .default_width(460.0)
+            .show(ctx, |ui| {
+                let Some(ref watcher) = self.watcher else {
+                    ui.label("No log is being watched");
+                    return;
+                };
+
+                ui.label(format!("Split index: {}", watcher.split_index()));
+                ui.label(format!("Waiting for: {}", watcher.waiting_for()));
+                ui.separator();
+
+                egui::ScrollArea::vertical()
+                    .stick_to_bottom(true)
+                    .auto_shrink(false)
+                    .show(ui, |ui| {
+                        for line in watcher.debug_lines() {
+                            let text = if let Some(ref error) = line.error {
+                                egui::RichText::new(format!("{}  → {}", line.line, error)).color(color(theme.error))
+                            } else if line.matched.is_empty() {
+                                egui::RichText::new(&line.line).color(color(theme.text_dim))
+                            } else {
+                                egui::RichText::new(format!("{}  → {}", line.line, line.matched.join(", ")))
+                                    .color(color(theme.ahead_gaining))
+                            };
+                            ui.label(text.monospace().size(11.0));
+                        }
+                    });
             });
+        self.show_debug = show_debug;
+
+        // Statistics per segment, to pick what to practice
+        if let Some((names, stats, attempts)) = stats {
+            let mut show_stats = self.show_stats;
+            egui::Window::new("Statistics")
+                .open(&mut show_stats)
+                .default_width(520.0)
+                .show(ctx, |ui| {
+                    ui.label(format!("From {} attempts and any practice", attempts));
+                    ui.separator();
+
+                    let time = |t: Option<f64>| egui::RichText::new(t.map(stats::format_seconds).unwrap_or_else(|| "-".to_string())).monospace();
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        egui::Grid::new("segment_stats").striped(true).show(ui, |ui| {
+                            for header in ["Segment", "Gold", "Average", "Median", "Std dev", "Resets", "Lost"] {
+                                ui.label(egui::RichText::new(header).strong());
+                            }
+                            ui.end_row();
+
+                            for (name, segment) in names.iter().zip(&stats) {
+                                ui.label(name);
+                                ui.label(time(segment.gold).color(color(theme.best_segment)));
+                                ui.label(time(segment.average));
+                                ui.label(time(segment.median));
+                                ui.label(time(segment.std_dev));
+                                let resets = segment.reset_rate.map(|r| format!("{:.0}%", r * 100.0));
+                                ui.label(egui::RichText::new(resets.unwrap_or_else(|| "-".to_string())).monospace());
+                                ui.label(time(segment.time_lost).color(color(theme.behind_losing)));
+                                ui.end_row();
+                            }
+                        });
+                    });
+                });
+            self.show_stats = show_stats;
+        }
+    }
+}
+
+/// The split icons as textures. Icons that don't load are left out.
+fn load_icons(ctx: &egui::Context, splits_file: &SplitsFile, dir: &Path) -> Vec<Option<egui::TextureHandle>> {
+    splits_file
+        .splits
+        .iter()
+        .map(|split| {
+            let path = dir.join(split.icon.as_ref()?);
+            let icon = image::load_from_memory(&std::fs::read(&path).ok()?).ok()?.to_rgba8();
+            let image = egui::ColorImage::from_rgba_unmultiplied([icon.width() as usize, icon.height() as usize], &icon);
+            Some(ctx.load_texture(path.to_string_lossy(), image, egui::TextureOptions::LINEAR))
+        })
+        .collect()
+}
+
+/// `--layout` if given, else the game folder's layout, else the default one
+fn choose_layout(layout_path: Option<&Path>, game_dir: Option<&Path>) -> Result<Layout, Box<dyn std::error::Error>> {
+    let game_layout = game_dir
+        .map(|dir| dir.join(GAME_LAYOUT_FILE))
+        .filter(|path| path.exists());
+    match layout_path.map(Path::to_path_buf).or(game_layout) {
+        Some(path) => layout::load(&path),
+        None => Ok(layout::default_layout()),
+    }
+}
+
+pub(crate) fn color(c: Rgb) -> egui::Color32 {
+    egui::Color32::from_rgb(c.r, c.g, c.b)
+}
+
+/// egui's fonts with the theme's font, if it has one, in front
+fn font_definitions(theme: &Theme) -> egui::FontDefinitions {
+    let mut fonts = egui::FontDefinitions::default();
+    // A font that can't be read leaves egui's own
+    if let Some(bytes) = theme.font.as_ref().and_then(|path| std::fs::read(path).ok()) {
+        fonts.font_data.insert("theme".to_string(), egui::FontData::from_owned(bytes));
+        for family in [egui::FontFamily::Proportional, egui::FontFamily::Monospace] {
+            fonts.families.entry(family).or_default().insert(0, "theme".to_string());
+        }
     }
+    fonts
+}
+
+/// Load a `.wasm` autosplitter onto the shared timer and show game time,
+/// which the autosplitter controls.
+fn attach_wasm(
+    path: &Path,
+    timer: &SharedTimer,
+) -> Result<WasmAutosplitter, Box<dyn std::error::Error>> {
+    let wasm = WasmAutosplitter::load(path, timer.clone())?;
+    timer
+        .write()
+        .unwrap()
+        .set_current_timing_method(TimingMethod::GameTime);
+    Ok(wasm)
 }
 
 pub fn run_gui(
     splits_path: Option<PathBuf>,
     watch_path: Option<PathBuf>,
+    autosplitter_path: Option<PathBuf>,
+    record_path: Option<PathBuf>,
+    layout_path: Option<PathBuf>,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let app = LiveSplitApp::new(splits_path, watch_path)?;
+    let app = LiveSplitApp::new(splits_path, watch_path, autosplitter_path, record_path, layout_path)?;
+
+    // Always transparent, so the background can be turned off while running
+    let window = &app.preferences.window;
+    let mut viewport = egui::ViewportBuilder::default()
+        .with_inner_size(window.size.unwrap_or([300.0, 500.0]))
+        .with_min_inner_size([250.0, 350.0])
+        .with_title("MacSplit")
+        .with_decorations(!window.borderless)
+        .with_transparent(true);
+    if window.always_on_top {
+        viewport = viewport.with_always_on_top();
+    }
+    if let Some(position) = window.position {
+        viewport = viewport.with_position(position);
+    }
 
     let options = eframe::NativeOptions {
-        viewport: egui::ViewportBuilder::default()
-            .with_inner_size([300.0, 500.0])
-            .with_min_inner_size([250.0, 350.0])
-            .with_title("MacSplit"),
+        viewport,
         ..Default::default()
     };
 
@@ -605,6 +1162,152 @@ pub fn run_gui(
     Ok(())
 }
 
+/// Segment times in seconds of every attempt in the run's history, up to
+/// where the attempt ended, None for skipped splits
+fn history_segment_times(run: &Run, method: TimingMethod) -> Vec<Vec<Option<f64>>> {
+    run.attempt_history()
+        .iter()
+        .map(|attempt| {
+            run.segments()
+                .iter()
+                .map_while(|s| s.segment_history().get(attempt.index()))
+                .map(|time| time[method].map(|t| t.total_seconds()))
+                .collect()
+        })
+        .collect()
+}
+
+/// A run in the graph: its delta against the comparison at every split it
+/// reached, None where it has no time
+pub(crate) struct GraphLine {
+    pub deltas: Vec<Option<f64>>,
+    pub segment_deltas: Vec<Option<f64>>, // Each segment against its best segment
+}
+
+/// Follow a run's split times through the comparison. A skipped split's
+/// segment is counted in the next one, which is then held against the best
+/// segments of both. The terminal UI colors its deltas from this too.
+pub(crate) fn graph_line(
+    split_times: &[Option<TimeSpan>],
+    comparison: &[Option<TimeSpan>],
+    best_segments: &[Option<TimeSpan>],
+) -> GraphLine {
+    let mut line = GraphLine {
+        deltas: Vec::new(),
+        segment_deltas: Vec::new(),
+    };
+    let mut previous_split = None;
+    let mut segment_start = 0;
+    for (i, &split) in split_times.iter().enumerate() {
+        let delta = match (split, comparison.get(i).copied().flatten()) {
+            (Some(split), Some(compared)) => Some(split.total_seconds() - compared.total_seconds()),
+            _ => None,
+        };
+        let segment = calculate_segment_time(split, previous_split);
+        line.deltas.push(delta);
+        line.segment_deltas.push(calculate_delta(segment, sum_of_best(best_segments, segment_start..=i)));
+        if split.is_some() {
+            previous_split = split;
+            segment_start = i + 1;
+        }
+    }
+    line
+}
+
+/// The best segments over `range` added up, None if any of them has no time
+fn sum_of_best(best_segments: &[Option<TimeSpan>], range: RangeInclusive<usize>) -> Option<TimeSpan> {
+    range
+        .map(|i| best_segments.get(i).copied().flatten())
+        .try_fold(TimeSpan::zero(), |sum, best| Some(sum + best?))
+}
+
+/// The last `past_attempts` attempts from the run's segment history followed
+/// by the current run, all against the timer's comparison
+fn graph_lines(timer: &Timer, past_attempts: usize) -> Vec<GraphLine> {
+    let run = timer.run();
+    let method = timer.current_timing_method();
+    let comparison: Vec<_> = run
+        .segments()
+        .iter()
+        .map(|s| s.comparison(timer.current_comparison())[method])
+        .collect();
+    let best_segments: Vec<_> = run.segments().iter().map(|s| s.best_segment_time()[method]).collect();
+
+    let attempts = history_segment_times(run, method);
+    let mut lines: Vec<GraphLine> = attempts[attempts.len().saturating_sub(past_attempts)..]
+        .iter()
+        .map(|segment_times| {
+            let mut total = 0.0;
+            let split_times: Vec<_> = segment_times
+                .iter()
+                .map(|segment| {
+                    segment.map(|segment| {
+                        total += segment;
+                        TimeSpan::from_seconds(total)
+                    })
+                })
+                .collect();
+            graph_line(&split_times, &comparison, &best_segments)
+        })
+        .collect();
+
+    let done = timer.current_split_index().unwrap_or(0).min(run.len());
+    let split_times: Vec<_> = run.segments()[..done].iter().map(|s| s.split_time()[method]).collect();
+    lines.push(graph_line(&split_times, &comparison, &best_segments));
+    lines
+}
+
+/// Plot the runs' deltas across the splits, behind the comparison above the
+/// middle line and ahead below it. The current run is colored like its
+/// deltas; earlier attempts are dimmed with their golds marked.
+fn show_graph(ui: &mut egui::Ui, lines: &[GraphLine], split_count: usize, theme: &Theme) {
+    let size = egui::vec2(ui.available_width(), GRAPH_HEIGHT);
+    let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 0.0, color(theme.split_background));
+
+    let rect = rect.shrink(6.0);
+    let range = lines
+        .iter()
+        .flat_map(|line| line.deltas.iter().flatten())
+        .fold(1.0_f64, |range, delta| range.max(delta.abs()));
+    let point = |split: usize, delta: f64| {
+        egui::pos2(
+            rect.left() + rect.width() * split as f32 / split_count.max(1) as f32,
+            rect.center().y - rect.height() / 2.0 * (delta / range) as f32,
+        )
+    };
+    painter.hline(rect.x_range(), rect.center().y, egui::Stroke::new(1.0, color(theme.text_dim)));
+
+    for (i, line) in lines.iter().enumerate() {
+        let current = i + 1 == lines.len();
+        let stroke = if current {
+            egui::Stroke::new(2.0, color(theme.text))
+        } else {
+            egui::Stroke::new(1.0, color(theme.text_dim).gamma_multiply(0.5))
+        };
+
+        // Every run starts even; skipped splits are bridged
+        let mut previous = point(0, 0.0);
+        let mut previous_delta = None;
+        for (split, (delta, segment_delta)) in line.deltas.iter().zip(&line.segment_deltas).enumerate() {
+            let Some(delta) = *delta else {
+                continue;
+            };
+            let p = point(split + 1, delta);
+            painter.line_segment([previous, p], stroke);
+            if current {
+                painter.circle_filled(p, 3.0, delta_color(delta, previous_delta, *segment_delta, theme));
+            } else if segment_delta.is_some_and(|d| d <= 0.0) {
+                // Segment history is taken into the best segments on reset
+                painter.circle_filled(p, 2.5, color(theme.best_segment));
+            }
+            previous = p;
+            previous_delta = Some(delta);
+        }
+    }
+}
+
 /// Calculate the segment time from cumulative split times
 ///
 /// # Arguments
@@ -638,26 +1341,25 @@ fn calculate_delta(current_segment: Option<TimeSpan>, best_segment: Option<TimeS
     }
 }
 
-/// Determine the color for a delta value
+/// Determine the color for a split's delta, the way LiveSplit does
 ///
 /// # Arguments
-/// * `delta` - The delta in seconds
+/// * `delta` - The run's delta against the comparison at this split
+/// * `previous_delta` - The delta at the previous split (None for the first)
+/// * `segment_delta` - This segment against the best segment (see `calculate_delta`)
+/// * `theme` - The theme to take the colors from
 ///
 /// # Returns
-/// Green if ahead (negative), Gold if close (0-1s behind), Red if behind (>1s)
-fn delta_color(delta: f64) -> egui::Color32 {
-    if delta < 0.0 {
-        TIME_GREEN
-    } else if delta < 1.0 {
-        TIME_GOLD
-    } else {
-        TIME_RED
-    }
+/// Gold for a new best segment, otherwise ahead/behind and gaining/losing
+fn delta_color(delta: f64, previous_delta: Option<f64>, segment_delta: Option<f64>, theme: &Theme) -> egui::Color32 {
+    let best_segment = segment_delta.is_some_and(|d| d < 0.0);
+    color(theme.delta_color(DeltaKind::of(delta, previous_delta, best_segment)))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use livesplit_core::Time;
 
     #[test]
     fn test_calculate_segment_time_first_segment() {
@@ -742,25 +1444,33 @@ mod tests {
 
     #[test]
     fn test_delta_color_ahead() {
-        // Negative delta = ahead = green
-        assert_eq!(delta_color(-5.0), TIME_GREEN);
-        assert_eq!(delta_color(-0.1), TIME_GREEN);
+        // Ahead of the comparison: green while gaining, lighter when losing time
+        let theme = Theme::dark();
+        assert_eq!(delta_color(-5.0, None, Some(1.0), &theme), color(theme.ahead_gaining));
+        assert_eq!(delta_color(-5.0, Some(-3.0), Some(1.0), &theme), color(theme.ahead_gaining));
+        assert_eq!(delta_color(-0.1, Some(-3.0), Some(1.0), &theme), color(theme.ahead_losing));
     }
 
     #[test]
-    fn test_delta_color_slightly_behind() {
-        // 0 to 1 second behind = gold
-        assert_eq!(delta_color(0.0), TIME_GOLD);
-        assert_eq!(delta_color(0.5), TIME_GOLD);
-        assert_eq!(delta_color(0.99), TIME_GOLD);
+    fn test_delta_color_behind() {
+        // Behind the comparison: red while losing, lighter when gaining time back
+        let theme = Theme::dark();
+        assert_eq!(delta_color(0.0, None, Some(1.0), &theme), color(theme.behind_losing));
+        assert_eq!(delta_color(5.0, Some(1.0), Some(1.0), &theme), color(theme.behind_losing));
+        assert_eq!(delta_color(0.5, Some(3.0), Some(1.0), &theme), color(theme.behind_gaining));
     }
 
     #[test]
-    fn test_delta_color_behind() {
-        // More than 1 second behind = red
-        assert_eq!(delta_color(1.0), TIME_RED);
-        assert_eq!(delta_color(5.0), TIME_RED);
-        assert_eq!(delta_color(100.0), TIME_RED);
+    fn test_delta_color_gold_only_for_best_segment() {
+        // A new best segment is gold whether ahead or behind
+        let theme = Theme::dark();
+        assert_eq!(delta_color(5.0, Some(8.0), Some(-0.5), &theme), color(theme.best_segment));
+        assert_eq!(delta_color(-2.0, None, Some(-0.1), &theme), color(theme.best_segment));
+        // Being slightly behind isn't
+        assert_ne!(delta_color(0.5, Some(0.2), Some(0.3), &theme), color(theme.best_segment));
+        // Nor is tying the best segment or having none
+        assert_ne!(delta_color(-1.0, None, Some(0.0), &theme), color(theme.best_segment));
+        assert_ne!(delta_color(-1.0, None, None, &theme), color(theme.best_segment));
     }
 
     #[test]
@@ -770,72 +1480,137 @@ mod tests {
         // Current times: 25s, 25s, 25s (segments)
         // Cumulative: 25s, 50s, 75s vs best cumulative would be 30s, 60s, 90s
 
-        let best_segments = vec![
+        let best_segments = [
             TimeSpan::from_seconds(30.0),
             TimeSpan::from_seconds(30.0),
             TimeSpan::from_seconds(30.0),
         ];
 
-        let current_splits = vec![
+        let current_splits = [
             TimeSpan::from_seconds(25.0),  // Split 1: 25s cumulative
             TimeSpan::from_seconds(50.0),  // Split 2: 50s cumulative
             TimeSpan::from_seconds(75.0),  // Split 3: 75s cumulative
         ];
 
+        let theme = Theme::dark();
         let mut prev_split: Option<TimeSpan> = None;
+        let mut prev_delta: Option<f64> = None;
+        let mut best_cumulative = 0.0;
 
         for (i, &current_split) in current_splits.iter().enumerate() {
             let segment_time = calculate_segment_time(Some(current_split), prev_split);
-            let delta = calculate_delta(segment_time, Some(best_segments[i]));
+            let segment_delta = calculate_delta(segment_time, Some(best_segments[i]));
 
             // All segments should be 5 seconds faster
-            assert!(delta.is_some());
-            assert!((delta.unwrap() - (-5.0)).abs() < 0.001,
-                "Segment {} delta should be -5.0, got {}", i, delta.unwrap());
+            assert!(segment_delta.is_some());
+            assert!((segment_delta.unwrap() - (-5.0)).abs() < 0.001,
+                "Segment {} delta should be -5.0, got {}", i, segment_delta.unwrap());
 
-            // Color should be green (ahead)
-            assert_eq!(delta_color(delta.unwrap()), TIME_GREEN);
+            // Every segment is a new best, so gold
+            best_cumulative += best_segments[i].total_seconds();
+            let delta = current_split.total_seconds() - best_cumulative;
+            assert_eq!(delta_color(delta, prev_delta, segment_delta, &theme), color(theme.best_segment));
 
             prev_split = Some(current_split);
+            prev_delta = Some(delta);
         }
     }
 
     #[test]
     fn test_full_run_scenario_mixed_performance() {
-        // Simulate a run with mixed performance
+        // Simulate a run with mixed performance, compared against a run
+        // with every best segment
         // Best segments: 30s, 30s, 30s
         // Current segments: 25s (fast), 35s (slow), 30s (same)
-        // Cumulative: 25s, 60s, 90s
+        // Cumulative: 25s, 60s, 90s vs 30s, 60s, 90s
 
-        let best_segments = vec![
+        let best_segments = [
             TimeSpan::from_seconds(30.0),
             TimeSpan::from_seconds(30.0),
             TimeSpan::from_seconds(30.0),
         ];
 
-        let current_splits = vec![
+        let current_splits = [
             TimeSpan::from_seconds(25.0),  // Segment 1: 25s (5s ahead)
             TimeSpan::from_seconds(60.0),  // Segment 2: 35s (5s behind)
             TimeSpan::from_seconds(90.0),  // Segment 3: 30s (even)
         ];
 
-        let expected_deltas = vec![-5.0, 5.0, 0.0];
-        let expected_colors = vec![TIME_GREEN, TIME_RED, TIME_GOLD];
+        let expected_deltas = [-5.0, 5.0, 0.0];
+        let theme = Theme::dark();
+        let expected_colors = [
+            color(theme.best_segment),  // New best segment
+            color(theme.behind_losing), // Even with the comparison after losing 5s
+            color(theme.behind_losing), // Still even; tying a best segment isn't gold
+        ];
 
         let mut prev_split: Option<TimeSpan> = None;
+        let mut prev_delta: Option<f64> = None;
+        let mut best_cumulative = 0.0;
 
         for (i, &current_split) in current_splits.iter().enumerate() {
             let segment_time = calculate_segment_time(Some(current_split), prev_split);
-            let delta = calculate_delta(segment_time, Some(best_segments[i]));
+            let segment_delta = calculate_delta(segment_time, Some(best_segments[i]));
 
-            assert!(delta.is_some());
-            assert!((delta.unwrap() - expected_deltas[i]).abs() < 0.001,
-                "Segment {} delta should be {}, got {}", i, expected_deltas[i], delta.unwrap());
+            assert!(segment_delta.is_some());
+            assert!((segment_delta.unwrap() - expected_deltas[i]).abs() < 0.001,
+                "Segment {} delta should be {}, got {}", i, expected_deltas[i], segment_delta.unwrap());
 
-            assert_eq!(delta_color(delta.unwrap()), expected_colors[i],
+            best_cumulative += best_segments[i].total_seconds();
+            let delta = current_split.total_seconds() - best_cumulative;
+            assert_eq!(delta_color(delta, prev_delta, segment_delta, &theme), expected_colors[i],
                 "Segment {} color mismatch", i);
 
             prev_split = Some(current_split);
+            prev_delta = Some(delta);
+        }
+    }
+
+    #[test]
+    fn test_graph_lines_without_personal_best() {
+        let time = |s: f64| Time::new().with_real_time(Some(TimeSpan::from_seconds(s)));
+        let mut run = Run::new();
+        for (name, segment_times) in [("Basement", [30.0_f64, 28.0]), ("Mom", [40.0, 45.0])] {
+            let mut segment = Segment::new(name);
+            segment.set_best_segment_time(time(segment_times[0].min(segment_times[1])));
+            for (attempt, seconds) in (1..).zip(segment_times) {
+                segment.segment_history_mut().insert(attempt, time(seconds));
+            }
+            run.push_segment(segment);
         }
+        for attempt in 1..=2 {
+            run.add_attempt_with_index(Time::new(), attempt, None, None, None);
+        }
+
+        let mut timer = Timer::new(run).unwrap();
+        layout::pick_comparison(&mut timer);
+        let lines = graph_lines(&timer, 2);
+
+        // Both earlier attempts against the sum of best segments: 28s and 68s
+        assert_eq!(lines.len(), 3);
+        assert!((lines[0].deltas[0].unwrap() - 2.0).abs() < 0.001);
+        assert!((lines[0].deltas[1].unwrap() - 2.0).abs() < 0.001);
+        assert!((lines[1].deltas[1].unwrap() - 5.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_graph_line_bridges_skipped_splits() {
+        let secs = |s: f64| Some(TimeSpan::from_seconds(s));
+        // PB splits at 30s, 60s and 90s, best segments of 28s each
+        let comparison = vec![secs(30.0), secs(60.0), secs(90.0)];
+        let best_segments = vec![secs(28.0), secs(28.0), secs(28.0)];
+
+        // Split 2 skipped: the third segment covers both
+        let line = graph_line(&[secs(27.0), None, secs(95.0)], &comparison, &best_segments);
+
+        assert_eq!(line.deltas.len(), 3);
+        assert!((line.deltas[0].unwrap() + 3.0).abs() < 0.001);
+        assert!(line.deltas[1].is_none());
+        assert!((line.deltas[2].unwrap() - 5.0).abs() < 0.001);
+
+        assert!(line.segment_deltas[0].unwrap() < 0.0); // Gold
+        assert!(line.segment_deltas[1].is_none());
+        // 68s against the 56s of both best segments
+        assert!((line.segment_deltas[2].unwrap() - 12.0).abs() < 0.001);
     }
 }
@@ -0,0 +1,303 @@
+use crate::watcher::Ending;
+use livesplit_core::run::{parser, saver};
+use livesplit_core::{Run, Timer, TimerPhase, TimingMethod};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::PathBuf;
+
+/// What the timer did when a run ended
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Policy {
+    #[default]
+    Reset, // Reset immediately
+    Stop,     // Pause the timer and wait for a manual reset
+    Continue, // Keep running (death-allowed categories)
+}
+
+impl Policy {
+    /// Act on a run that ended. A reset is left to the caller, which has
+    /// its watchers to move back, so this only says whether one is due.
+    pub fn apply(self, timer: &mut Timer) -> bool {
+        match self {
+            Policy::Reset => true,
+            Policy::Stop => {
+                if timer.current_phase() == TimerPhase::Running {
+                    timer.pause();
+                }
+                false
+            }
+            Policy::Continue => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Finished,
+    Reset,       // Autosplitter reset trigger
+    ManualReset, // Reset by the runner
+    Death,
+    Quit,
+    NewRun,
+    Closed, // MacSplit closed during the run
+}
+
+impl From<Ending> for Outcome {
+    fn from(ending: Ending) -> Self {
+        match ending {
+            Ending::Death => Outcome::Death,
+            Ending::Quit => Outcome::Quit,
+            Ending::NewRun => Outcome::NewRun,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttemptRecord {
+    pub ended_at: u64, // Unix time in seconds
+    pub outcome: Outcome,
+    #[serde(default)]
+    pub policy: Option<Policy>,
+    pub splits_completed: usize,
+    #[serde(default)]
+    pub time_ms: Option<u64>,
+}
+
+/// Appends how each attempt ended to a JSON lines file per game and
+/// category in the user's data directory. Next to it, the run's attempts and
+/// segment times are kept as a LiveSplit `.lss` for statistics, practice and
+/// the run graph.
+pub struct AttemptHistory {
+    path: Option<PathBuf>,
+    // The attempt already recorded, by its number in the run. Starting the
+    // timer counts a new attempt, which clears it.
+    recorded: Option<u32>,
+}
+
+impl AttemptHistory {
+    pub fn new(game: &str, category: &str) -> Self {
+        let path = dirs_next::data_dir().map(|dir| {
+            dir.join("MacSplit")
+                .join("history")
+                .join(format!("{}-{}.jsonl", slug(game), slug(category)))
+        });
+        Self { path, recorded: None }
+    }
+
+    /// Record the end of the attempt in progress, if there is one. An attempt
+    /// is recorded once: a stopped run that is reset later keeps its first
+    /// outcome. Endings the run continues through are recorded along the
+    /// way without ending it.
+    pub fn record(&mut self, timer: &Timer, outcome: Outcome, policy: Option<Policy>) {
+        let attempt = timer.run().attempt_count();
+        match timer.current_phase() {
+            TimerPhase::NotRunning => return,
+            _ if self.recorded == Some(attempt) => return,
+            // Finishing is recorded when the last split is hit
+            TimerPhase::Ended if outcome != Outcome::Finished => return,
+            _ => {}
+        }
+        if policy != Some(Policy::Continue) {
+            self.recorded = Some(attempt);
+        }
+
+        let time_ms = timer.snapshot().current_time()[timer.current_timing_method()]
+            .map(|t| (t.total_seconds() * 1000.0) as u64);
+        let splits_completed = timer.current_split_index().unwrap_or(0);
+        let ended_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        let record = AttemptRecord {
+            ended_at,
+            outcome,
+            policy,
+            splits_completed,
+            time_ms,
+        };
+
+        // History is best effort; never interrupt the timer over it
+        let _ = self.append(&record);
+    }
+
+    /// Add the attempts, segment times, golds and personal best saved by
+    /// `save_run` to a new run of the same splits. History of splits that have changed since
+    /// no longer lines up and is left out.
+    pub fn load_run(&self, run: &mut Run) {
+        let Some(saved) = self.read_run() else { return };
+        let names = |run: &Run| run.segments().iter().map(|s| s.name().to_string()).collect::<Vec<_>>();
+        if names(&saved) != names(run) {
+            return;
+        }
+
+        for attempt in saved.attempt_history() {
+            run.add_attempt_with_index(attempt.time(), attempt.index(), attempt.started(), attempt.ended(), attempt.pause_time());
+        }
+        run.set_attempt_count(saved.attempt_count());
+        for (segment, saved) in run.segments_mut().iter_mut().zip(saved.segments()) {
+            *segment.segment_history_mut() = saved.segment_history().clone();
+            segment.set_personal_best_split_time(saved.personal_best_split_time());
+            let best = segment.best_segment_time_mut();
+            for method in [TimingMethod::RealTime, TimingMethod::GameTime] {
+                if let Some(t) = saved.best_segment_time()[method] {
+                    if best[method].is_none_or(|b| t < b) {
+                        best[method] = Some(t);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Keep the run's attempts and segment times, practice included
+    pub fn save_run(&self, run: &Run) {
+        // History is best effort; never interrupt the timer over it
+        let _ = self.write_run(run);
+    }
+
+    fn run_path(&self) -> Option<PathBuf> {
+        self.path.as_ref().map(|path| path.with_extension("lss"))
+    }
+
+    fn read_run(&self) -> Option<Run> {
+        let source = std::fs::read_to_string(self.run_path()?).ok()?;
+        parser::livesplit::parse(&source).ok()
+    }
+
+    fn write_run(&self, run: &Run) -> Result<(), Box<dyn std::error::Error>> {
+        let path = self.run_path().ok_or("No data directory")?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let mut lss = String::new();
+        saver::livesplit::save_run(run, &mut lss)?;
+        std::fs::write(path, lss)?;
+        Ok(())
+    }
+
+    fn append(&self, record: &AttemptRecord) -> Result<(), Box<dyn std::error::Error>> {
+        let path = self.path.as_ref().ok_or("No data directory")?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        writeln!(file, "{}", serde_json::to_string(record)?)?;
+        Ok(())
+    }
+}
+
+fn slug(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '-' })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use livesplit_core::Segment;
+
+    fn history(name: &str) -> AttemptHistory {
+        let path = std::env::temp_dir().join(format!("macsplit-history-{}-{}.jsonl", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        AttemptHistory { path: Some(path), recorded: None }
+    }
+
+    fn read(history: &AttemptHistory) -> Vec<AttemptRecord> {
+        let source = std::fs::read_to_string(history.path.as_ref().unwrap()).unwrap_or_default();
+        source.lines().map(|line| serde_json::from_str(line).unwrap()).collect()
+    }
+
+    fn run_of(names: &[&str]) -> Run {
+        let mut run = Run::new();
+        for name in names {
+            run.push_segment(Segment::new(*name));
+        }
+        run
+    }
+
+    fn timer() -> Timer {
+        Timer::new(run_of(&["Basement", "Mom"])).unwrap()
+    }
+
+    #[test]
+    fn test_stopped_run_is_recorded_once() {
+        let mut history = history("stop");
+        let mut timer = timer();
+        timer.start();
+
+        history.record(&timer, Outcome::Death, Some(Policy::Stop));
+        assert!(!Policy::Stop.apply(&mut timer));
+        assert_eq!(timer.current_phase(), TimerPhase::Paused);
+        history.record(&timer, Outcome::ManualReset, None);
+        timer.reset(true);
+
+        let records = read(&history);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].outcome, Outcome::Death);
+        assert_eq!(records[0].policy, Some(Policy::Stop));
+
+        // The next attempt is recorded again
+        timer.start();
+        history.record(&timer, Outcome::ManualReset, None);
+        assert_eq!(read(&history).len(), 2);
+    }
+
+    #[test]
+    fn test_continued_run_is_recorded_once() {
+        let mut history = history("continue");
+        let mut timer = timer();
+        timer.start();
+
+        history.record(&timer, Outcome::Death, Some(Policy::Continue));
+        assert!(!Policy::Continue.apply(&mut timer));
+        assert_eq!(timer.current_phase(), TimerPhase::Running);
+        timer.split();
+        timer.split();
+        assert_eq!(timer.current_phase(), TimerPhase::Ended);
+        history.record(&timer, Outcome::Finished, None);
+
+        let records = read(&history);
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].outcome, Outcome::Death);
+        assert_eq!(records[0].policy, Some(Policy::Continue));
+        assert_eq!(records[1].outcome, Outcome::Finished);
+
+        // Finishing was the end of it
+        history.record(&timer, Outcome::ManualReset, None);
+        assert_eq!(read(&history).len(), 2);
+    }
+
+    #[test]
+    fn test_run_history_is_kept_across_sessions() {
+        let history = history("run");
+        let mut timer = timer();
+        timer.start();
+        timer.split();
+        timer.split();
+        timer.reset(true);
+        history.save_run(timer.run());
+
+        let mut run = run_of(&["Basement", "Mom"]);
+        history.load_run(&mut run);
+        assert_eq!(run.attempt_count(), 1);
+        assert_eq!(run.attempt_history().len(), 1);
+        assert_eq!(run.segment(1).segment_history().iter().count(), 1);
+        assert!(run.segment(1).best_segment_time().real_time.is_some());
+        assert!(run.segment(1).personal_best_split_time().real_time.is_some());
+
+        // Different splits start over
+        let mut run = run_of(&["Basement", "Satan"]);
+        history.load_run(&mut run);
+        assert_eq!(run.attempt_count(), 0);
+        assert!(run.attempt_history().is_empty());
+        let _ = std::fs::remove_file(history.run_path().unwrap());
+    }
+}
@@ -0,0 +1,386 @@
+use crate::gui::color;
+use crate::splits::display_name;
+use crate::theme::{Rgb, Theme};
+use eframe::egui;
+use livesplit_core::component::splits::{
+    ColumnKind, ColumnSettings, ColumnStartWith, ColumnUpdateTrigger, ColumnUpdateWith, TimeColumn,
+};
+use livesplit_core::comparison::{best_segments, personal_best};
+use livesplit_core::component::{graph, splits, text, timer, title};
+use livesplit_core::layout::{Component, ComponentState, LayoutState};
+use livesplit_core::settings::{Color, Gradient};
+use livesplit_core::{Layout, Timer};
+use std::path::Path;
+
+/// Splits the default layout shows, enough to fill the default window
+const DEFAULT_VISIBLE_SPLITS: usize = 10;
+
+/// Comparison for the times of upcoming splits, which every run of the
+/// splits has. Deltas are against the timer's comparison, see
+/// `pick_comparison`.
+const BEST_SEGMENTS: &str = "Best Segments";
+
+/// Compare against the personal best once the run has one, and against the
+/// sum of best segments until then, which history always fills in
+pub fn pick_comparison(timer: &mut Timer) {
+    let method = timer.current_timing_method();
+    let has_pb = timer
+        .run()
+        .segments()
+        .last()
+        .is_some_and(|segment| segment.personal_best_split_time()[method].is_some());
+    let _ = timer.set_current_comparison(if has_pb { personal_best::NAME } else { best_segments::NAME });
+}
+
+/// Load a LiveSplit `.lsl` layout
+pub fn load(path: &Path) -> Result<Layout, Box<dyn std::error::Error>> {
+    let source = std::fs::read_to_string(path)?;
+    let layout = livesplit_core::layout::parser::parse(&source)
+        .map_err(|e| format!("Bad layout {}: {}", path.display(), e))?;
+    Ok(layout)
+}
+
+/// Title, splits and timer: split times with the delta against the personal
+/// best (or the sum of best segments without one), colored ahead/behind,
+/// gaining/losing or gold for a new best segment, and best segments for the
+/// splits still to come.
+pub fn default_layout() -> Layout {
+    let mut layout = Layout::new();
+    layout.push(title::Component::new());
+
+    let column = |name: &str, start_with, update_with, comparison: Option<&str>| ColumnSettings {
+        name: name.to_string(),
+        kind: ColumnKind::Time(TimeColumn {
+            start_with,
+            update_with,
+            update_trigger: ColumnUpdateTrigger::OnEndingSegment,
+            comparison_override: comparison.map(str::to_string),
+            timing_method: None,
+        }),
+    };
+    let mut splits = splits::Component::new();
+    // Columns are listed right to left
+    splits.settings_mut().columns = vec![
+        column("Time", ColumnStartWith::ComparisonSegmentTime, ColumnUpdateWith::SplitTime, Some(BEST_SEGMENTS)),
+        column("+/−", ColumnStartWith::Empty, ColumnUpdateWith::Delta, None),
+    ];
+    layout.push(splits);
+    apply_split_window(&mut layout, DEFAULT_VISIBLE_SPLITS, 1);
+
+    layout.push(timer::Component::new());
+    layout
+}
+
+/// Color the layout's components with the theme, replacing the colors a
+/// `.lsl` layout brought
+pub fn apply_theme(layout: &mut Layout, theme: &Theme) {
+    let rgba = |c: Rgb| Color::rgba(c.r as f32 / 255.0, c.g as f32 / 255.0, c.b as f32 / 255.0, 1.0);
+    let general = layout.general_settings_mut();
+    general.background = Gradient::Plain(rgba(theme.background));
+    general.text_color = rgba(theme.text);
+    general.ahead_gaining_time_color = rgba(theme.ahead_gaining);
+    general.ahead_losing_time_color = rgba(theme.ahead_losing);
+    general.behind_gaining_time_color = rgba(theme.behind_gaining);
+    general.behind_losing_time_color = rgba(theme.behind_losing);
+    general.best_segment_color = rgba(theme.best_segment);
+    general.not_running_color = rgba(theme.not_running);
+    general.paused_color = rgba(theme.paused);
+    general.personal_best_color = rgba(theme.personal_best);
+}
+
+/// Show `visible` splits (0 for all) in every splits component, following
+/// the current split with `preview` upcoming ones and keeping the final split
+/// in view
+pub fn apply_split_window(layout: &mut Layout, visible: usize, preview: usize) {
+    for component in &mut layout.components {
+        if let Component::Splits(splits) = component {
+            let settings = splits.settings_mut();
+            settings.visual_split_count = visible;
+            settings.split_preview_count = preview;
+            settings.always_show_last_split = true;
+        }
+    }
+}
+
+fn color32(color: Color) -> egui::Color32 {
+    let [r, g, b, a] = color.to_rgba8();
+    egui::Color32::from_rgba_unmultiplied(r, g, b, a)
+}
+
+/// Draw the components of a layout state, top to bottom, with the icons of
+/// the run's splits
+pub fn show(ui: &mut egui::Ui, state: &LayoutState, theme: &Theme, icons: &[Option<egui::TextureHandle>]) {
+    let text = color32(state.text_color);
+    let dim = color(theme.text_dim);
+
+    for component in &state.components {
+        match component {
+            ComponentState::Title(title) => {
+                let title_color = title.text_color.map(color32).unwrap_or(text);
+                egui::Frame::none()
+                    .fill(color(theme.header_background))
+                    .inner_margin(egui::Margin::symmetric(12.0, 8.0))
+                    .show(ui, |ui| {
+                        ui.set_width(ui.available_width());
+                        ui.vertical_centered(|ui| {
+                            if let Some(line) = title.line1.first() {
+                                ui.label(egui::RichText::new(line.to_string()).size(theme.text_size + 4.0).strong().color(title_color));
+                            }
+                            if let Some(line) = title.line2.first() {
+                                ui.label(egui::RichText::new(line.to_string()).size(theme.text_size).color(dim));
+                            }
+                        });
+                    });
+                ui.add_space(2.0);
+            }
+            ComponentState::Splits(splits) => {
+                for split in &splits.splits {
+                    let bg = if split.is_current_split {
+                        theme.current_split_background
+                    } else {
+                        theme.split_background
+                    };
+                    egui::Frame::none()
+                        .fill(color(bg))
+                        .inner_margin(egui::Margin::symmetric(10.0, 6.0))
+                        .show(ui, |ui| {
+                            ui.horizontal(|ui| {
+                                // Subsplits are named with a leading "-"
+                                if split.name.starts_with('-') {
+                                    ui.add_space(12.0);
+                                }
+                                let name = display_name(&split.name);
+                                if let Some(Some(icon)) = icons.get(split.index) {
+                                    let size = egui::Vec2::splat(theme.text_size + 4.0);
+                                    ui.add(egui::Image::new((icon.id(), size)));
+                                }
+                                let name_color = if split.is_current_split { text } else { dim };
+                                ui.label(egui::RichText::new(name.as_ref()).size(theme.text_size).color(name_color));
+
+                                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                    for column in &split.columns {
+                                        ui.label(
+                                            egui::RichText::new(column.value.as_str())
+                                                .size(theme.text_size)
+                                                .color(color32(column.visual_color))
+                                                .monospace(),
+                                        );
+                                        ui.add_space(6.0);
+                                    }
+                                });
+                            });
+                        });
+                    ui.add_space(1.0);
+                }
+                ui.add_space(4.0);
+            }
+            ComponentState::Timer(timer) => {
+                egui::Frame::none()
+                    .fill(color(theme.header_background))
+                    .inner_margin(egui::Margin::symmetric(12.0, 16.0))
+                    .show(ui, |ui| {
+                        ui.set_width(ui.available_width());
+                        ui.vertical_centered(|ui| timer_label(ui, timer, theme.timer_size));
+                    });
+                ui.add_space(4.0);
+            }
+            ComponentState::KeyValue(row) => {
+                let key_color = row.key_color.map(color32).unwrap_or(text);
+                let value_color = row.value_color.map(color32).unwrap_or(text);
+                key_value(ui, &row.key, &row.value, key_color, value_color, theme);
+            }
+            ComponentState::Text(state) => {
+                let left = state.left_center_color.map(color32).unwrap_or(text);
+                match &state.text {
+                    text::TextState::Center(line) => {
+                        egui::Frame::none()
+                            .fill(color(theme.split_background))
+                            .inner_margin(egui::Margin::symmetric(10.0, 4.0))
+                            .show(ui, |ui| {
+                                ui.set_width(ui.available_width());
+                                ui.vertical_centered(|ui| {
+                                    ui.label(egui::RichText::new(line.as_str()).size(theme.text_size - 1.0).color(left));
+                                });
+                            });
+                        ui.add_space(1.0);
+                    }
+                    text::TextState::Split(key, value) => {
+                        let right = state.right_color.map(color32).unwrap_or(text);
+                        key_value(ui, key, value, left, right, theme);
+                    }
+                }
+            }
+            ComponentState::DetailedTimer(detailed) => {
+                egui::Frame::none()
+                    .fill(color(theme.header_background))
+                    .inner_margin(egui::Margin::symmetric(12.0, 8.0))
+                    .show(ui, |ui| {
+                        ui.set_width(ui.available_width());
+                        ui.horizontal(|ui| {
+                            ui.vertical(|ui| {
+                                if let Some(ref name) = detailed.segment_name {
+                                    ui.label(egui::RichText::new(name.as_str()).size(theme.text_size).color(dim));
+                                }
+                                for comparison in [&detailed.comparison1, &detailed.comparison2].into_iter().flatten() {
+                                    ui.label(
+                                        egui::RichText::new(format!("{}: {}", comparison.name, comparison.time))
+                                            .size(theme.text_size - 1.0)
+                                            .color(dim),
+                                    );
+                                }
+                            });
+                            ui.with_layout(egui::Layout::top_down(egui::Align::Max), |ui| {
+                                timer_label(ui, &detailed.timer, theme.timer_size);
+                                timer_label(ui, &detailed.segment_timer, theme.timer_size / 2.0);
+                            });
+                        });
+                    });
+                ui.add_space(4.0);
+            }
+            ComponentState::Graph(graph) => {
+                show_graph(ui, graph);
+                ui.add_space(4.0);
+            }
+            ComponentState::Separator(_) => {
+                ui.separator();
+            }
+            ComponentState::BlankSpace(_) => {
+                ui.add_space(8.0);
+            }
+        }
+    }
+}
+
+/// A label on the left and a value on the right, like "Sum of Best"
+fn key_value(ui: &mut egui::Ui, key: &str, value: &str, key_color: egui::Color32, value_color: egui::Color32, theme: &Theme) {
+    egui::Frame::none()
+        .fill(color(theme.split_background))
+        .inner_margin(egui::Margin::symmetric(10.0, 4.0))
+        .show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.label(egui::RichText::new(key).size(theme.text_size - 1.0).color(key_color));
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    ui.label(egui::RichText::new(value).size(theme.text_size - 1.0).color(value_color).monospace());
+                });
+            });
+        });
+    ui.add_space(1.0);
+}
+
+fn timer_label(ui: &mut egui::Ui, timer: &timer::State, size: f32) {
+    ui.label(
+        egui::RichText::new(format!("{}{}", timer.time, timer.fraction))
+            .size(size)
+            .strong()
+            .color(color32(timer.top_color))
+            .monospace(),
+    );
+}
+
+/// LiveSplit's graph component. Its coordinates are fractions of the
+/// component's size, with the x-axis at `middle`.
+fn show_graph(ui: &mut egui::Ui, graph: &graph::State) {
+    let size = egui::vec2(ui.available_width(), graph.height as f32);
+    let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    let at = |x: f32, y: f32| rect.left_top() + egui::vec2(x * rect.width(), y * rect.height());
+
+    let middle = at(1.0, graph.middle);
+    painter.rect_filled(egui::Rect::from_min_max(rect.left_top(), middle), 0.0, color32(graph.top_background_color));
+    painter.rect_filled(egui::Rect::from_min_max(at(0.0, graph.middle), rect.right_bottom()), 0.0, color32(graph.bottom_background_color));
+
+    let grid = egui::Stroke::new(1.0, color32(graph.grid_lines_color));
+    for &y in &graph.horizontal_grid_lines {
+        painter.line_segment([at(0.0, y), at(1.0, y)], grid);
+    }
+    for &x in &graph.vertical_grid_lines {
+        painter.line_segment([at(x, 0.0), at(x, 1.0)], grid);
+    }
+
+    for pair in graph.points.windows(2) {
+        let line_color = if pair[1].is_best_segment { graph.best_segment_color } else { graph.graph_lines_color };
+        painter.line_segment([at(pair[0].x, pair[0].y), at(pair[1].x, pair[1].y)], egui::Stroke::new(2.0, color32(line_color)));
+    }
+    for point in graph.points.iter().skip(1) {
+        let point_color = if point.is_best_segment { graph.best_segment_color } else { graph.graph_lines_color };
+        painter.circle_filled(at(point.x, point.y), 2.5, color32(point_color));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use livesplit_core::{Run, Segment, Time, TimeSpan, Timer, TimingMethod};
+
+    #[test]
+    fn test_default_layout_colors_deltas() {
+        let mut run = Run::new();
+        for name in ["Stage 1", "Stage 2"] {
+            let mut segment = Segment::new(name);
+            segment.set_best_segment_time(Time::new().with_game_time(Some(TimeSpan::from_seconds(30.0))));
+            run.push_segment(segment);
+        }
+        let mut timer = Timer::new(run).unwrap();
+        timer.set_current_timing_method(TimingMethod::GameTime);
+        pick_comparison(&mut timer);
+        timer.start();
+        timer.initialize_game_time();
+        timer.pause_game_time();
+
+        // A gold, then losing 10s on the second segment
+        timer.set_game_time(TimeSpan::from_seconds(25.0));
+        timer.split();
+        timer.set_game_time(TimeSpan::from_seconds(65.0));
+        timer.split();
+
+        let theme = Theme::dark();
+        let mut layout = default_layout();
+        apply_theme(&mut layout, &theme);
+        let state = layout.state(&timer.snapshot());
+        let Some(ComponentState::Splits(splits)) = state.components.get(1) else {
+            panic!("no splits component");
+        };
+
+        let delta = |i: usize| &splits.splits[i].columns[1];
+        assert_eq!(delta(0).value, "−5.0");
+        assert_eq!(color32(delta(0).visual_color), color(theme.best_segment));
+        assert_eq!(delta(1).value, "+5.0");
+        assert_eq!(color32(delta(1).visual_color), color(theme.behind_losing));
+    }
+
+    #[test]
+    fn test_default_layout_shows_every_delta_color_against_the_personal_best() {
+        let seconds = |s: f64| Time::new().with_game_time(Some(TimeSpan::from_seconds(s)));
+        let mut run = Run::new();
+        for (i, name) in ["Stage 1", "Stage 2", "Stage 3", "Stage 4"].into_iter().enumerate() {
+            let mut segment = Segment::new(name);
+            segment.set_best_segment_time(seconds(20.0));
+            segment.set_personal_best_split_time(seconds(30.0 * (i + 1) as f64));
+            run.push_segment(segment);
+        }
+        let mut timer = Timer::new(run).unwrap();
+        timer.set_current_timing_method(TimingMethod::GameTime);
+        pick_comparison(&mut timer);
+        timer.start();
+        timer.initialize_game_time();
+        timer.pause_game_time();
+        for split in [27.0, 59.0, 92.0, 121.0] {
+            timer.set_game_time(TimeSpan::from_seconds(split));
+            timer.split();
+        }
+
+        let theme = Theme::dark();
+        let mut layout = default_layout();
+        apply_theme(&mut layout, &theme);
+        let state = layout.state(&timer.snapshot());
+        let Some(ComponentState::Splits(splits)) = state.components.get(1) else {
+            panic!("no splits component");
+        };
+
+        let colors: Vec<_> = (0..4).map(|i| color32(splits.splits[i].columns[1].visual_color)).collect();
+        assert_eq!(
+            colors,
+            [theme.ahead_gaining, theme.ahead_losing, theme.behind_losing, theme.behind_gaining].map(color)
+        );
+    }
+}
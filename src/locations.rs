@@ -0,0 +1,274 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Where a game writes its log, as given in `config.json`: one path, a list
+/// of fallbacks tried in order, or either of those per OS (keyed `macos`,
+/// `linux`, `windows`).
+///
+/// Paths may start with `~`, use `$VAR`/`${VAR}` (the XDG base directories
+/// fall back to their defaults when unset) and `{proton:<appid>}` for the
+/// game's Proton prefix in any Steam library. Relative paths are relative to
+/// the home directory. A path passed with `--watch` is used as it is.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum LogLocation {
+    One(String),
+    Fallbacks(Vec<String>),
+    PerOs(HashMap<String, Candidates>),
+    #[serde(skip)]
+    Literal(PathBuf),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Candidates {
+    One(String),
+    Fallbacks(Vec<String>),
+}
+
+impl Candidates {
+    fn strs(&self) -> Vec<&str> {
+        match self {
+            Candidates::One(p) => vec![p.as_str()],
+            Candidates::Fallbacks(ps) => ps.iter().map(|p| p.as_str()).collect(),
+        }
+    }
+}
+
+impl LogLocation {
+    fn candidates(&self) -> Vec<&str> {
+        match self {
+            LogLocation::One(p) => vec![p.as_str()],
+            LogLocation::Fallbacks(ps) => ps.iter().map(|p| p.as_str()).collect(),
+            LogLocation::PerOs(by_os) => by_os
+                .get(std::env::consts::OS)
+                .map(Candidates::strs)
+                .unwrap_or_default(),
+            LogLocation::Literal(_) => Vec::new(),
+        }
+    }
+
+    /// The first candidate that exists. If none exists yet, the first
+    /// candidate, so errors can say where the log was expected.
+    pub fn resolve(&self) -> Option<PathBuf> {
+        if let LogLocation::Literal(path) = self {
+            return Some(path.clone());
+        }
+
+        let home = dirs_next::home_dir().unwrap_or_else(|| PathBuf::from("/"));
+        let env = |name: &str| std::env::var(name).ok();
+        let libraries = steam_libraries(&home);
+
+        let paths: Vec<PathBuf> = self
+            .candidates()
+            .into_iter()
+            .flat_map(|c| expand(c, &home, &env, &libraries))
+            .collect();
+
+        paths
+            .iter()
+            .find(|p| p.is_file())
+            .or(paths.first())
+            .cloned()
+    }
+}
+
+/// Expand one candidate into the paths it stands for. A `{proton:<appid>}`
+/// placeholder gives one path per Steam library; an unset variable gives
+/// none.
+fn expand(
+    candidate: &str,
+    home: &Path,
+    env: &dyn Fn(&str) -> Option<String>,
+    steam_libraries: &[PathBuf],
+) -> Vec<PathBuf> {
+    let Some(expanded) = expand_vars(candidate, home, env) else {
+        return Vec::new();
+    };
+
+    let expanded = match expanded.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => {
+            format!("{}{}", home.display(), rest)
+        }
+        _ => expanded,
+    };
+
+    let with_prefixes: Vec<String> = match proton_placeholder(&expanded) {
+        Some((placeholder, app_id)) => steam_libraries
+            .iter()
+            .map(|library| {
+                let prefix = library
+                    .join("steamapps/compatdata")
+                    .join(app_id)
+                    .join("pfx");
+                expanded.replace(placeholder, &prefix.to_string_lossy())
+            })
+            .collect(),
+        None => vec![expanded],
+    };
+
+    with_prefixes
+        .into_iter()
+        .map(|p| {
+            let path = PathBuf::from(p);
+            if path.is_absolute() {
+                path
+            } else {
+                home.join(path)
+            }
+        })
+        .collect()
+}
+
+/// Replace `$VAR` and `${VAR}`. Returns None if a variable is unset.
+fn expand_vars(
+    candidate: &str,
+    home: &Path,
+    env: &dyn Fn(&str) -> Option<String>,
+) -> Option<String> {
+    let mut out = String::new();
+    let mut rest = candidate;
+
+    while let Some(dollar) = rest.find('$') {
+        out.push_str(&rest[..dollar]);
+        let after = &rest[dollar + 1..];
+
+        let (name, remainder) = match after.strip_prefix('{') {
+            Some(braced) => {
+                let end = braced.find('}')?;
+                (&braced[..end], &braced[end + 1..])
+            }
+            None => {
+                let end = after
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .unwrap_or(after.len());
+                (&after[..end], &after[end..])
+            }
+        };
+
+        if name.is_empty() {
+            out.push('$');
+        } else {
+            out.push_str(&env(name).or_else(|| xdg_default(name, home))?);
+        }
+        rest = remainder;
+    }
+
+    out.push_str(rest);
+    Some(out)
+}
+
+fn xdg_default(name: &str, home: &Path) -> Option<String> {
+    let dir = match name {
+        "XDG_DATA_HOME" => ".local/share",
+        "XDG_CONFIG_HOME" => ".config",
+        "XDG_STATE_HOME" => ".local/state",
+        "XDG_CACHE_HOME" => ".cache",
+        "HOME" => "",
+        _ => return None,
+    };
+    Some(home.join(dir).to_string_lossy().trim_end_matches('/').to_string())
+}
+
+/// Find `{proton:<appid>}`, returning the placeholder and the app id
+fn proton_placeholder(path: &str) -> Option<(&str, &str)> {
+    let start = path.find("{proton:")?;
+    let end = start + path[start..].find('}')?;
+    Some((&path[start..=end], &path[start + "{proton:".len()..end]))
+}
+
+/// Steam library folders: the usual Steam roots plus every library listed
+/// in their `libraryfolders.vdf`.
+fn steam_libraries(home: &Path) -> Vec<PathBuf> {
+    let roots = [
+        home.join(".steam/steam"),
+        home.join(".local/share/Steam"),
+        home.join(".var/app/com.valvesoftware.Steam/.local/share/Steam"),
+    ];
+
+    let mut libraries: Vec<PathBuf> = Vec::new();
+    for root in roots.iter().filter(|r| r.is_dir()) {
+        let vdf = std::fs::read_to_string(root.join("steamapps/libraryfolders.vdf")).unwrap_or_default();
+        let listed = vdf.lines().filter_map(|line| {
+            // "path"		"/mnt/games/SteamLibrary"
+            let mut fields = line.split('"').skip(1).step_by(2);
+            if fields.next()? != "path" {
+                return None;
+            }
+            fields.next().map(PathBuf::from)
+        });
+
+        for library in std::iter::once(root.clone()).chain(listed) {
+            let library = library.canonicalize().unwrap_or(library);
+            if !libraries.contains(&library) {
+                libraries.push(library);
+            }
+        }
+    }
+
+    libraries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env(name: &str) -> Option<String> {
+        match name {
+            "GAMES" => Some("/mnt/games".to_string()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_expands_home_env_and_xdg_defaults() {
+        let home = Path::new("/home/runner");
+        let paths = |c| expand(c, home, &env, &[]);
+
+        assert_eq!(paths("~/isaac/log.txt"), vec![PathBuf::from("/home/runner/isaac/log.txt")]);
+        assert_eq!(paths("$GAMES/log.txt"), vec![PathBuf::from("/mnt/games/log.txt")]);
+        assert_eq!(
+            paths("${XDG_DATA_HOME}/binding of isaac rebirth/log.txt"),
+            vec![PathBuf::from("/home/runner/.local/share/binding of isaac rebirth/log.txt")]
+        );
+        // Relative paths stay relative to home; unknown variables drop the candidate
+        assert_eq!(paths("Library/log.txt"), vec![PathBuf::from("/home/runner/Library/log.txt")]);
+        assert!(paths("$MISSING/log.txt").is_empty());
+    }
+
+    #[test]
+    fn test_proton_prefix_in_every_library() {
+        let home = Path::new("/home/runner");
+        let libraries = [PathBuf::from("/home/runner/.steam/steam"), PathBuf::from("/mnt/games/SteamLibrary")];
+
+        assert_eq!(
+            expand("{proton:250900}/drive_c/log.txt", home, &env, &libraries),
+            vec![
+                PathBuf::from("/home/runner/.steam/steam/steamapps/compatdata/250900/pfx/drive_c/log.txt"),
+                PathBuf::from("/mnt/games/SteamLibrary/steamapps/compatdata/250900/pfx/drive_c/log.txt"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_per_os_locations_parse() {
+        let location: LogLocation = serde_json::from_str(
+            r#"{ "macos": "Library/log.txt", "linux": ["~/.local/log.txt", "{proton:1}/log.txt"] }"#,
+        )
+        .unwrap();
+
+        let expected: Vec<&str> = match std::env::consts::OS {
+            "macos" => vec!["Library/log.txt"],
+            "linux" => vec!["~/.local/log.txt", "{proton:1}/log.txt"],
+            _ => vec![],
+        };
+        assert_eq!(location.candidates(), expected);
+    }
+
+    #[test]
+    fn test_literal_paths_are_not_expanded() {
+        let path = PathBuf::from("/tmp/~/$MACSPLIT_UNSET_VARIABLE/{proton:1}/log.txt");
+        assert_eq!(LogLocation::Literal(path.clone()).resolve(), Some(path));
+    }
+}
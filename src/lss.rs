@@ -0,0 +1,138 @@
+use crate::splits::SplitsFile;
+use livesplit_core::run::{parser, saver};
+use livesplit_core::settings::Image;
+use livesplit_core::{Run, Segment, Time, TimeSpan};
+use std::path::Path;
+
+/// Write splits as a LiveSplit `.lss` file, with best segments and the split
+/// icons embedded
+pub fn export(splits_path: &Path, output: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let splits_file = SplitsFile::load(splits_path)
+        .map_err(|e| format!("Bad splits file {}: {}", splits_path.display(), e))?;
+    let dir = splits_path.parent().unwrap_or(Path::new("."));
+
+    let mut run = Run::new();
+    run.set_game_name(splits_file.game.as_str());
+    run.set_category_name(splits_file.category.as_str());
+
+    for split in &splits_file.splits {
+        let mut segment = Segment::new(&split.name);
+        if let Some(best_ms) = split.best_time_ms {
+            let best = Some(TimeSpan::from_milliseconds(best_ms as f64));
+            segment.set_best_segment_time(Time::new().with_real_time(best).with_game_time(best));
+        }
+        if let Some(ref icon) = split.icon {
+            let path = dir.join(icon);
+            let data = std::fs::read(&path)
+                .map_err(|e| format!("Could not read icon {}: {}", path.display(), e))?;
+            segment.set_icon(Image::new(&data));
+        }
+        run.push_segment(segment);
+    }
+
+    let mut lss = String::new();
+    saver::livesplit::save_run(&run, &mut lss)?;
+    std::fs::write(output, lss)?;
+    Ok(())
+}
+
+/// Turn a LiveSplit `.lss` file into a draft splits.json at `output`. Split
+/// icons are written next to it as `icon-<n>.png`, converted to PNG if they
+/// are in another format.
+pub fn import(lss_path: &Path, output: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let source = std::fs::read_to_string(lss_path)?;
+    let run = parser::livesplit::parse(&source)
+        .map_err(|e| format!("Bad splits {}: {}", lss_path.display(), e))?;
+    let dir = output.parent().unwrap_or(Path::new("."));
+
+    let mut splits = Vec::new();
+    for (i, segment) in run.segments().iter().enumerate() {
+        let mut split = serde_json::json!({ "name": segment.name() });
+        if let Some(best) = segment.best_segment_time().real_time {
+            split["best_time_ms"] = serde_json::json!(best.total_milliseconds().round() as u64);
+        }
+        let icon = segment.icon().data();
+        if !icon.is_empty() {
+            match png_icon(icon) {
+                Some(png) => {
+                    let name = format!("icon-{}.png", i + 1);
+                    std::fs::write(dir.join(&name), png)?;
+                    split["icon"] = serde_json::json!(name);
+                }
+                None => eprintln!("Left out the icon of {}, its format isn't supported", segment.name()),
+            }
+        }
+        splits.push(split);
+    }
+
+    let draft = serde_json::json!({
+        "game": run.game_name(),
+        "category": run.category_name(),
+        "splits": splits,
+    });
+    std::fs::write(output, serde_json::to_string_pretty(&draft)?)?;
+    Ok(())
+}
+
+/// Icon data as PNG, the format the GUI reads
+fn png_icon(data: &[u8]) -> Option<Vec<u8>> {
+    if image::guess_format(data).ok()? == image::ImageFormat::Png {
+        return Some(data.to_vec());
+    }
+    let mut png = Vec::new();
+    image::load_from_memory(data)
+        .ok()?
+        .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+        .ok()?;
+    Some(png)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The 8-byte PNG signature is enough for the icon to survive the round trip
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+    #[test]
+    fn test_icons_survive_export_and_import() {
+        let dir = std::env::temp_dir().join(format!("macsplit-lss-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("icons")).unwrap();
+        std::fs::write(dir.join("icons").join("boss.png"), PNG).unwrap();
+        std::fs::write(
+            dir.join("splits.json"),
+            r#"{ "game": "Game", "category": "Any%", "splits": [
+                { "name": "Stage 1", "best_time_ms": 61500 },
+                { "name": "Boss", "icon": "icons/boss.png" }
+            ] }"#,
+        )
+        .unwrap();
+
+        export(&dir.join("splits.json"), &dir.join("run.lss")).unwrap();
+        let imported = dir.join("imported").join("splits.json");
+        std::fs::create_dir_all(imported.parent().unwrap()).unwrap();
+        import(&dir.join("run.lss"), &imported).unwrap();
+
+        let splits = SplitsFile::load(&imported).unwrap();
+        assert_eq!(splits.game, "Game");
+        assert_eq!(splits.splits[0].best_time_ms, Some(61500));
+        assert_eq!(splits.splits[0].icon, None);
+        assert_eq!(splits.splits[1].icon.as_deref(), Some(Path::new("icon-2.png")));
+        assert_eq!(std::fs::read(dir.join("imported").join("icon-2.png")).unwrap(), PNG);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_icons_are_converted_to_png() {
+        let mut bmp = Vec::new();
+        image::RgbaImage::new(2, 2)
+            .write_to(&mut std::io::Cursor::new(&mut bmp), image::ImageFormat::Bmp)
+            .unwrap();
+
+        let png = png_icon(&bmp).unwrap();
+        assert_eq!(image::guess_format(&png).unwrap(), image::ImageFormat::Png);
+        assert_eq!(png_icon(PNG).as_deref(), Some(PNG));
+        assert_eq!(png_icon(b"not an image"), None);
+    }
+}
@@ -1,9 +1,24 @@
+mod history;
+mod layout;
+mod locations;
+mod lss;
+mod memory;
+mod packages;
+mod practice;
+mod screen;
+mod script;
+mod session;
+mod settings;
 mod splits;
+mod stats;
+mod suggest;
+mod theme;
 mod watcher;
 mod timer_app;
+mod wasm;
 mod gui;
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use std::path::PathBuf;
 
 #[derive(Parser)]
@@ -18,18 +33,142 @@ struct Args {
     #[arg(short, long)]
     watch: Option<PathBuf>,
 
+    /// Path to a LiveSplit WebAssembly (.wasm) autosplitter
+    #[arg(short, long)]
+    autosplitter: Option<PathBuf>,
+
     /// Use terminal UI instead of GUI
     #[arg(short, long)]
     terminal: bool,
+
+    /// Terminal UI that also shows the watched log lines and trigger matches
+    #[arg(long)]
+    debug_watch: bool,
+
+    /// LiveSplit layout (.lsl) for the GUI
+    #[arg(long)]
+    layout: Option<PathBuf>,
+
+    /// Record the log lines and timer actions of this session to a file
+    #[arg(long, value_name = "PATH")]
+    record: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Install an autosplitter bundle (a folder or .zip with a manifest.json)
+    Install {
+        /// Path to the bundle
+        path: PathBuf,
+    },
+
+    /// Export splits.json as a LiveSplit .lss file, icons included
+    ExportLss {
+        /// The splits.json to export
+        splits: PathBuf,
+
+        /// Defaults to the splits file with an .lss extension
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Import a LiveSplit .lss file as a draft splits.json, icons included
+    ImportLss {
+        /// The .lss file to import
+        lss: PathBuf,
+
+        /// Defaults to splits.json next to the .lss file
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Overwrite the output if it exists
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Suggest trigger keywords from a timed log and the times split was pressed
+    SuggestTriggers {
+        /// Timed log (one `<seconds><TAB><line>` entry per line) or a
+        /// `--record` session (.jsonl)
+        log: PathBuf,
+
+        /// Times split was pressed, comma separated, as seconds or m:ss.s.
+        /// Taken from the session when not given.
+        #[arg(long, value_delimiter = ',', value_parser = parse_time_arg)]
+        splits_at: Vec<f64>,
+
+        /// Time the run was started, to also suggest a start trigger
+        #[arg(long, value_parser = parse_time_arg)]
+        start_at: Option<f64>,
+
+        /// Split names, comma separated
+        #[arg(long, value_delimiter = ',')]
+        names: Vec<String>,
+
+        #[arg(long, default_value = "Game")]
+        game: String,
+
+        #[arg(long, default_value = "Any%")]
+        category: String,
+
+        /// Write the draft splits.json here instead of printing it
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+fn parse_time_arg(text: &str) -> Result<f64, String> {
+    suggest::parse_time(text).ok_or_else(|| format!("bad time {:?}", text))
 }
 
 fn main() {
     let args = Args::parse();
 
-    let result = if args.terminal {
-        timer_app::run(args.splits, args.watch)
-    } else {
-        gui::run_gui(args.splits, args.watch)
+    let result = match args.command {
+        Some(Command::Install { path }) => {
+            packages::install(&path).map(|dir| {
+                println!("Installed to {}", dir.display());
+                for broken in packages::discover_autosplitters().broken {
+                    println!("Not loaded: {}: {}", broken.dir.display(), broken.error);
+                }
+            })
+        }
+        Some(Command::ExportLss { splits, output }) => {
+            let output = output.unwrap_or_else(|| splits.with_extension("lss"));
+            lss::export(&splits, &output).map(|()| println!("Exported to {}", output.display()))
+        }
+        Some(Command::ImportLss { lss, output, force }) => {
+            let output = output.unwrap_or_else(|| lss.with_file_name("splits.json"));
+            if output.exists() && !force {
+                Err(format!("{} already exists, pass --force to overwrite it", output.display()).into())
+            } else {
+                lss::import(&lss, &output).map(|()| println!("Imported to {}", output.display()))
+            }
+        }
+        Some(Command::SuggestTriggers { log, splits_at, start_at, names, game, category, output }) => {
+            let options = suggest::SuggestOptions {
+                split_times: splits_at,
+                start_time: start_at,
+                names,
+                game,
+                category,
+                window: suggest::Window::default(),
+            };
+            suggest::run(&log, &options).and_then(|draft| match output {
+                Some(path) => std::fs::write(path, draft).map_err(Into::into),
+                None => {
+                    println!("{}", draft);
+                    Ok(())
+                }
+            })
+        }
+        None if args.terminal || args.debug_watch => {
+            timer_app::run(args.splits, args.watch, args.autosplitter, args.debug_watch, args.record)
+        }
+        None => gui::run_gui(args.splits, args.watch, args.autosplitter, args.record, args.layout),
     };
 
     if let Err(e) = result {
@@ -0,0 +1,524 @@
+use crate::watcher::WatchEvent;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// How often to look for the game process while not attached
+const ATTACH_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ValueType {
+    U8,
+    U16,
+    #[default]
+    U32,
+    I32,
+    U64,
+}
+
+impl ValueType {
+    fn size(self) -> usize {
+        match self {
+            ValueType::U8 => 1,
+            ValueType::U16 => 2,
+            ValueType::U32 | ValueType::I32 => 4,
+            ValueType::U64 => 8,
+        }
+    }
+
+    fn decode(self, bytes: &[u8]) -> i64 {
+        let mut buf = [0u8; 8];
+        buf[..bytes.len()].copy_from_slice(bytes);
+        let raw = u64::from_le_bytes(buf);
+        match self {
+            ValueType::I32 => raw as u32 as i32 as i64,
+            _ => raw as i64,
+        }
+    }
+}
+
+/// A value read from the game's memory. The address is the base of `module`
+/// plus the first offset; each further offset dereferences a 64-bit pointer
+/// and adds the offset to it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryValue {
+    pub name: String,
+    pub module: String,
+    pub offsets: Vec<u64>,
+    #[serde(default, rename = "type")]
+    pub value_type: ValueType,
+}
+
+/// Fires when `value` changes, optionally only when it changes to `equals`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryTrigger {
+    pub value: String,
+    #[serde(default)]
+    pub equals: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryConfig {
+    pub process: String,
+    pub values: Vec<MemoryValue>,
+    #[serde(default)]
+    pub start: Option<MemoryTrigger>,
+    #[serde(default)]
+    pub split: Option<MemoryTrigger>,
+    #[serde(default)]
+    pub reset: Option<MemoryTrigger>,
+}
+
+impl MemoryConfig {
+    /// The `memory` section of the `config.json` in `dir`, if there is one
+    pub fn load_for_dir(dir: &Path) -> Result<Option<Self>, Box<dyn std::error::Error>> {
+        #[derive(Deserialize)]
+        struct GameConfig {
+            #[serde(default)]
+            memory: Option<MemoryConfig>,
+        }
+
+        let path = dir.join("config.json");
+        if !path.exists() {
+            return Ok(None);
+        }
+        let config: GameConfig = serde_json::from_str(&std::fs::read_to_string(&path)?)
+            .map_err(|e| format!("Bad {}: {}", path.display(), e))?;
+        Ok(config.memory)
+    }
+}
+
+/// Reads values from a running game process and turns changes into the same
+/// events `LogWatcher` produces. Only supported on Linux.
+pub struct MemoryWatcher {
+    config: MemoryConfig,
+    pid: Option<i32>,
+    start_time: u64, // Of the attached process, so a new one reusing its pid isn't taken for it
+    module_bases: HashMap<String, u64>,
+    previous: HashMap<String, i64>,
+    current: HashMap<String, i64>,
+    current_split: usize,
+    last_attach_attempt: Option<Instant>,
+}
+
+impl MemoryWatcher {
+    pub fn new(config: MemoryConfig) -> Result<Self, Box<dyn std::error::Error>> {
+        if !cfg!(target_os = "linux") {
+            return Err("Memory autosplitters are only supported on Linux".into());
+        }
+
+        Ok(Self {
+            config,
+            pid: None,
+            start_time: 0,
+            module_bases: HashMap::new(),
+            previous: HashMap::new(),
+            current: HashMap::new(),
+            current_split: 0,
+            last_attach_attempt: None,
+        })
+    }
+
+    pub fn is_attached(&self) -> bool {
+        self.pid.is_some()
+    }
+
+    /// The process the watcher attaches to
+    pub fn process_name(&self) -> &str {
+        &self.config.process
+    }
+
+    pub fn set_split_index(&mut self, index: usize) {
+        self.current_split = index;
+    }
+
+    pub fn poll(&mut self) -> Vec<WatchEvent> {
+        if self.pid.is_none() && !self.try_attach() {
+            return Vec::new();
+        }
+
+        if !self.read_values() {
+            // Process exited or its memory became unreadable
+            self.detach();
+            return Vec::new();
+        }
+
+        let mut events = Vec::new();
+
+        if let Some(ref trigger) = self.config.reset {
+            if self.fired(trigger) {
+                self.current_split = 0;
+                events.push(WatchEvent::Reset);
+            }
+        }
+
+        if let Some(ref trigger) = self.config.start {
+            if self.fired(trigger) {
+                events.push(WatchEvent::Start);
+            }
+        }
+
+        if let Some(ref trigger) = self.config.split {
+            if self.fired(trigger) {
+                events.push(WatchEvent::Split(self.current_split));
+                self.current_split += 1;
+            }
+        }
+
+        events
+    }
+
+    fn fired(&self, trigger: &MemoryTrigger) -> bool {
+        let (Some(previous), Some(current)) = (
+            self.previous.get(&trigger.value),
+            self.current.get(&trigger.value),
+        ) else {
+            return false;
+        };
+
+        previous != current && trigger.equals.is_none_or(|e| e == *current)
+    }
+
+    fn try_attach(&mut self) -> bool {
+        if let Some(last) = self.last_attach_attempt {
+            if last.elapsed() < ATTACH_INTERVAL {
+                return false;
+            }
+        }
+        self.last_attach_attempt = Some(Instant::now());
+
+        process::find_pid(&self.config.process).is_some_and(|pid| self.attach_pid(pid))
+    }
+
+    /// Returns false if the process exited before it could be attached to
+    fn attach_pid(&mut self, pid: i32) -> bool {
+        let Some(start_time) = process::start_time(pid) else {
+            return false;
+        };
+        self.pid = Some(pid);
+        self.start_time = start_time;
+        self.module_bases.clear();
+        self.previous.clear();
+        self.current.clear();
+        true
+    }
+
+    fn detach(&mut self) {
+        self.pid = None;
+        self.module_bases.clear();
+    }
+
+    /// Read every configured value. Returns false if the process is gone.
+    fn read_values(&mut self) -> bool {
+        let Some(pid) = self.pid else {
+            return false;
+        };
+        if process::start_time(pid) != Some(self.start_time) {
+            return false;
+        }
+
+        self.previous = std::mem::take(&mut self.current);
+
+        for value in &self.config.values {
+            let base = match self.module_bases.get(&value.module) {
+                Some(base) => *base,
+                None => match process::module_base(pid, &value.module) {
+                    Some(base) => {
+                        self.module_bases.insert(value.module.clone(), base);
+                        base
+                    }
+                    // Module not loaded yet
+                    None => continue,
+                },
+            };
+
+            if let Some(v) = read_pointer_path(pid, base, value) {
+                self.current.insert(value.name.clone(), v);
+            }
+        }
+
+        true
+    }
+}
+
+fn read_pointer_path(pid: i32, base: u64, value: &MemoryValue) -> Option<i64> {
+    let (first, rest) = value.offsets.split_first()?;
+    let mut address = base.wrapping_add(*first);
+
+    for offset in rest {
+        let mut pointer = [0u8; 8];
+        process::read(pid, address, &mut pointer)?;
+        address = u64::from_le_bytes(pointer).wrapping_add(*offset);
+    }
+
+    let mut bytes = vec![0u8; value.value_type.size()];
+    process::read(pid, address, &mut bytes)?;
+    Some(value.value_type.decode(&bytes))
+}
+
+#[cfg(target_os = "linux")]
+mod process {
+    use std::path::Path;
+
+    /// Longest process name the kernel keeps in `/proc/<pid>/comm`
+    const COMM_LEN: usize = 15;
+
+    pub fn find_pid(name: &str) -> Option<i32> {
+        let wanted: String = name.chars().take(COMM_LEN).collect();
+
+        std::fs::read_dir("/proc")
+            .ok()?
+            .flatten()
+            .filter_map(|entry| entry.file_name().to_str()?.parse::<i32>().ok())
+            .find(|pid| {
+                std::fs::read_to_string(format!("/proc/{}/comm", pid))
+                    .map(|comm| comm.trim_end() == wanted)
+                    .unwrap_or(false)
+            })
+    }
+
+    /// When the process started, in clock ticks since boot. A pid that is
+    /// reused by another process gets a different start time.
+    pub fn start_time(pid: i32) -> Option<u64> {
+        parse_start_time(&std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?)
+    }
+
+    /// Field 22 of `/proc/<pid>/stat`. The name in field 2 can hold spaces
+    /// and parentheses, so fields are counted from its closing one.
+    pub(super) fn parse_start_time(stat: &str) -> Option<u64> {
+        let (_, fields) = stat.rsplit_once(')')?;
+        fields.split_whitespace().nth(19)?.parse().ok()
+    }
+
+    /// Start address of the first mapping of `module` in `/proc/<pid>/maps`
+    pub fn module_base(pid: i32, module: &str) -> Option<u64> {
+        let maps = std::fs::read_to_string(format!("/proc/{}/maps", pid)).ok()?;
+
+        maps.lines().find_map(|line| {
+            // address perms offset dev inode pathname
+            let mut fields = line.split_whitespace();
+            let range = fields.next()?;
+            let pathname = fields.nth(4)?;
+            let file_name = Path::new(pathname).file_name()?.to_str()?;
+            if file_name != module {
+                return None;
+            }
+            let start = range.split('-').next()?;
+            u64::from_str_radix(start, 16).ok()
+        })
+    }
+
+    pub fn read(pid: i32, address: u64, buf: &mut [u8]) -> Option<()> {
+        let local = libc::iovec {
+            iov_base: buf.as_mut_ptr() as *mut libc::c_void,
+            iov_len: buf.len(),
+        };
+        let remote = libc::iovec {
+            iov_base: address as *mut libc::c_void,
+            iov_len: buf.len(),
+        };
+
+        // SAFETY: `local` points at `buf`, which is valid for `buf.len()`
+        // bytes; the kernel validates the remote range.
+        let read = unsafe { libc::process_vm_readv(pid, &local, 1, &remote, 1, 0) };
+        (read == buf.len() as isize).then_some(())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod process {
+    pub fn find_pid(_name: &str) -> Option<i32> {
+        None
+    }
+
+    pub fn start_time(_pid: i32) -> Option<u64> {
+        None
+    }
+
+    pub fn module_base(_pid: i32, _module: &str) -> Option<u64> {
+        None
+    }
+
+    pub fn read(_pid: i32, _address: u64, _buf: &mut [u8]) -> Option<()> {
+        None
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Lines, Write};
+    use std::process::{Child, ChildStdout, Command, Stdio};
+    use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+
+    /// Set in the child process that plays the game
+    const FIXTURE_ENV: &str = "MACSPLIT_MEMORY_FIXTURE";
+
+    // The fixture's globals: a static pointer to a heap value, like a game's
+    // global pointing at its state struct, and a plain static flag.
+    static STAGE_POINTER: AtomicU64 = AtomicU64::new(0);
+    static RUN_STARTED: AtomicU32 = AtomicU32::new(0);
+
+    fn exe_name() -> String {
+        std::env::current_exe()
+            .unwrap()
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .to_string()
+    }
+
+    /// Offset of a static from the start of the test binary. The fixture runs
+    /// the same binary, so the offset is the same in its memory.
+    fn static_offset<T>(value: &T) -> u64 {
+        let base = process::module_base(std::process::id() as i32, &exe_name()).unwrap();
+        value as *const T as u64 - base
+    }
+
+    /// The game: sets its values from "stage 2" / "started 1" lines on stdin
+    /// and answers each with "ok"
+    #[test]
+    #[ignore = "run by test_reads_pointer_paths_and_emits_events"]
+    fn fixture_game() {
+        if std::env::var_os(FIXTURE_ENV).is_none() {
+            return;
+        }
+        let stage: &'static AtomicU32 = Box::leak(Box::new(AtomicU32::new(1)));
+        STAGE_POINTER.store(stage as *const AtomicU32 as u64, Ordering::SeqCst);
+        println!("ready");
+
+        for line in std::io::stdin().lines() {
+            let line = line.unwrap();
+            match line.split_once(' ') {
+                Some(("stage", v)) => stage.store(v.parse().unwrap(), Ordering::SeqCst),
+                Some(("started", v)) => RUN_STARTED.store(v.parse().unwrap(), Ordering::SeqCst),
+                _ => break,
+            }
+            println!("ok");
+        }
+    }
+
+    struct Fixture {
+        child: Child,
+        output: Lines<BufReader<ChildStdout>>,
+    }
+
+    impl Fixture {
+        fn spawn() -> Self {
+            let mut child = Command::new(std::env::current_exe().unwrap())
+                .args(["--exact", "memory::tests::fixture_game", "--ignored", "--nocapture"])
+                .env(FIXTURE_ENV, "1")
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .spawn()
+                .unwrap();
+            let output = BufReader::new(child.stdout.take().unwrap()).lines();
+            let mut fixture = Self { child, output };
+            fixture.wait_for("ready");
+            fixture
+        }
+
+        fn pid(&self) -> i32 {
+            self.child.id() as i32
+        }
+
+        fn set(&mut self, name: &str, value: u32) {
+            let stdin = self.child.stdin.as_mut().unwrap();
+            writeln!(stdin, "{} {}", name, value).unwrap();
+            self.wait_for("ok");
+        }
+
+        /// Skip the test harness's own output until `reply`. The harness
+        /// doesn't end its "test ... " line, so the first reply shares it.
+        fn wait_for(&mut self, reply: &str) {
+            let found = self.output.by_ref().map_while(Result::ok).any(|l| l.ends_with(reply));
+            assert!(found, "fixture exited before {:?}", reply);
+        }
+    }
+
+    impl Drop for Fixture {
+        fn drop(&mut self) {
+            let _ = self.child.kill();
+            let _ = self.child.wait();
+        }
+    }
+
+    #[test]
+    fn test_reads_pointer_paths_and_emits_events() {
+        let mut game = Fixture::spawn();
+
+        let config = MemoryConfig {
+            process: exe_name(),
+            values: vec![
+                MemoryValue {
+                    name: "stage".to_string(),
+                    module: exe_name(),
+                    offsets: vec![static_offset(&STAGE_POINTER), 0],
+                    value_type: ValueType::U32,
+                },
+                MemoryValue {
+                    name: "started".to_string(),
+                    module: exe_name(),
+                    offsets: vec![static_offset(&RUN_STARTED)],
+                    value_type: ValueType::U32,
+                },
+            ],
+            start: Some(MemoryTrigger { value: "started".to_string(), equals: Some(1) }),
+            split: Some(MemoryTrigger { value: "stage".to_string(), equals: None }),
+            reset: None,
+        };
+
+        let mut watcher = MemoryWatcher::new(config).unwrap();
+        assert!(watcher.attach_pid(game.pid()));
+
+        // First read only establishes a baseline
+        assert!(watcher.poll().is_empty());
+        assert!(watcher.is_attached());
+
+        game.set("started", 1);
+        assert!(matches!(watcher.poll()[..], [WatchEvent::Start]));
+
+        game.set("stage", 2);
+        assert!(matches!(watcher.poll()[..], [WatchEvent::Split(0)]));
+        assert!(watcher.poll().is_empty());
+
+        game.set("stage", 3);
+        assert!(matches!(watcher.poll()[..], [WatchEvent::Split(1)]));
+
+        // The game exiting detaches
+        drop(game);
+        assert!(watcher.poll().is_empty());
+        assert!(!watcher.is_attached());
+    }
+
+    #[test]
+    fn test_start_time_tells_reused_pids_apart() {
+        let stat = "4242 (a) game (x) S 1 4242 4242 0 -1 4194560 100 0 0 0 5 2 0 0 20 0 1 0 987654 0";
+        assert_eq!(process::parse_start_time(stat), Some(987654));
+
+        let pid = std::process::id() as i32;
+        let mut watcher = MemoryWatcher::new(MemoryConfig {
+            process: exe_name(),
+            values: Vec::new(),
+            start: None,
+            split: None,
+            reset: None,
+        })
+        .unwrap();
+        assert!(watcher.attach_pid(pid));
+        assert!(watcher.read_values());
+
+        // Another process that got the same pid
+        watcher.start_time += 1;
+        assert!(!watcher.read_values());
+    }
+
+    #[test]
+    fn test_value_types_decode_little_endian() {
+        assert_eq!(ValueType::U16.decode(&[0x34, 0x12]), 0x1234);
+        assert_eq!(ValueType::I32.decode(&(-5i32).to_le_bytes()), -5);
+        assert_eq!(ValueType::U32.decode(&u32::MAX.to_le_bytes()), u32::MAX as i64);
+    }
+}
@@ -0,0 +1,348 @@
+use crate::locations::LogLocation;
+use crate::memory::MemoryConfig;
+use crate::settings::SettingDefinition;
+use crate::splits::SplitsFile;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::path::{Path, PathBuf};
+
+/// Version of this build, checked against `min_macsplit_version`
+const MACSPLIT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GameConfig {
+    pub game: String,
+    #[serde(default)]
+    pub log_location: Option<LogLocation>,
+    #[serde(default)]
+    pub wasm: Option<String>, // .wasm autosplitter, relative to the game folder
+    #[serde(default)]
+    pub memory: Option<MemoryConfig>,
+    #[serde(default)]
+    pub settings: Vec<SettingDefinition>,
+}
+
+/// `manifest.json` of an autosplitter folder. Optional for folders shipped
+/// next to the executable, required for installed bundles.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    pub id: String,
+    pub version: String,
+    #[serde(default)]
+    pub author: Option<String>,
+    #[serde(default)]
+    pub game_versions: Vec<String>, // Game versions the autosplitter was made for
+    #[serde(default)]
+    pub min_macsplit_version: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct AvailableGame {
+    pub display_name: String,
+    pub folder_name: String, // Manifest id, or the folder's name without one
+    pub dir: PathBuf,
+    pub config: GameConfig,
+    pub manifest: Option<Manifest>,
+}
+
+impl AvailableGame {
+    /// Read an autosplitter folder, checking it has everything it needs
+    fn load(dir: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let config: GameConfig = serde_json::from_str(&std::fs::read_to_string(dir.join("config.json"))?)
+            .map_err(|e| format!("Bad config.json: {}", e))?;
+        SplitsFile::load(&dir.join("splits.json")).map_err(|e| format!("Bad splits.json: {}", e))?;
+
+        let manifest_path = dir.join("manifest.json");
+        let manifest: Option<Manifest> = if manifest_path.exists() {
+            let manifest: Manifest = serde_json::from_str(&std::fs::read_to_string(&manifest_path)?)
+                .map_err(|e| format!("Bad manifest.json: {}", e))?;
+            parse_version(&manifest.version).ok_or_else(|| format!("Bad version {:?}", manifest.version))?;
+            Some(manifest)
+        } else {
+            None
+        };
+
+        if let Some(min) = manifest.as_ref().and_then(|m| m.min_macsplit_version.as_ref()) {
+            if compare_versions(min, MACSPLIT_VERSION) == Ordering::Greater {
+                return Err(format!("Needs MacSplit {} or newer", min).into());
+            }
+        }
+
+        let folder_name = match manifest {
+            Some(ref m) => m.id.clone(),
+            None => dir
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default(),
+        };
+
+        Ok(Self {
+            display_name: config.game.clone(),
+            folder_name,
+            dir: dir.to_path_buf(),
+            config,
+            manifest,
+        })
+    }
+
+    fn version(&self) -> &str {
+        self.manifest.as_ref().map(|m| m.version.as_str()).unwrap_or("0")
+    }
+}
+
+/// An autosplitter folder that didn't load, and why
+#[derive(Debug, Clone)]
+pub struct BrokenGame {
+    pub dir: PathBuf,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Autosplitters {
+    pub games: Vec<AvailableGame>,
+    pub broken: Vec<BrokenGame>, // Folders with a config.json or manifest.json that don't load
+}
+
+/// Installed autosplitters, under the user's data directory
+pub fn user_autosplitters_dir() -> Option<PathBuf> {
+    dirs_next::data_dir().map(|dir| dir.join("MacSplit").join("autosplitters"))
+}
+
+/// Autosplitter directories, in order of preference for equal versions
+fn autosplitter_dirs() -> Vec<PathBuf> {
+    let exe_dir = std::env::current_exe()
+        .ok()
+        .and_then(|p| p.parent().map(|p| p.to_path_buf()))
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("autosplitters");
+
+    user_autosplitters_dir()
+        .into_iter()
+        .chain([PathBuf::from("autosplitters"), exe_dir])
+        .collect()
+}
+
+/// Every usable autosplitter, and the ones that don't load. When the same
+/// one is in several directories, the highest version wins.
+pub fn discover_autosplitters() -> Autosplitters {
+    discover_in(&autosplitter_dirs())
+}
+
+fn discover_in(dirs: &[PathBuf]) -> Autosplitters {
+    let mut found = Autosplitters::default();
+
+    for dir in dirs {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            continue;
+        };
+
+        let mut folders: Vec<PathBuf> = entries.flatten().map(|e| e.path()).filter(|p| p.is_dir()).collect();
+        folders.sort();
+
+        for path in folders {
+            let game = match AvailableGame::load(&path) {
+                Ok(game) => game,
+                // Other folders aren't autosplitters at all
+                Err(e) if path.join("config.json").exists() || path.join("manifest.json").exists() => {
+                    found.broken.push(BrokenGame { dir: path, error: e.to_string() });
+                    continue;
+                }
+                Err(_) => continue,
+            };
+            match found.games.iter_mut().find(|g| g.folder_name == game.folder_name) {
+                Some(existing) => {
+                    if compare_versions(game.version(), existing.version()) == Ordering::Greater {
+                        *existing = game;
+                    }
+                }
+                None => found.games.push(game),
+            }
+        }
+    }
+
+    found
+}
+
+/// Validate an autosplitter bundle (a folder or a .zip of one) and copy it
+/// into the user autosplitter directory. Returns where it was installed.
+pub fn install(source: &Path) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let user_dir = user_autosplitters_dir().ok_or("No data directory to install into")?;
+    let staging = std::env::temp_dir().join(format!("macsplit-install-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&staging);
+
+    let bundle = if source.is_dir() {
+        Ok(source.to_path_buf())
+    } else {
+        extract_zip(source, &staging)
+    };
+    let result = bundle.and_then(|bundle| install_bundle(&bundle, &user_dir));
+
+    let _ = std::fs::remove_dir_all(&staging);
+    result
+}
+
+fn install_bundle(bundle: &Path, user_dir: &Path) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let game = AvailableGame::load(bundle)?;
+    let manifest = game.manifest.as_ref().ok_or("Bundle has no manifest.json")?;
+    if manifest.id.is_empty() || manifest.id.contains(['/', '\\']) || manifest.id.starts_with('.') {
+        return Err(format!("Bad autosplitter id {:?}", manifest.id).into());
+    }
+
+    // Never downgrade an installed autosplitter
+    let target = user_dir.join(&manifest.id);
+    if let Ok(installed) = AvailableGame::load(&target) {
+        if compare_versions(installed.version(), &manifest.version) == Ordering::Greater {
+            return Err(format!(
+                "{} {} is already installed, newer than {}",
+                manifest.id,
+                installed.version(),
+                manifest.version
+            )
+            .into());
+        }
+    }
+
+    if target.exists() {
+        std::fs::remove_dir_all(&target)?;
+    }
+    copy_dir(bundle, &target)?;
+    Ok(target)
+}
+
+/// Extract a zip into `dir`, returning the bundle folder: `dir` itself, or
+/// the zip's single top-level folder.
+fn extract_zip(path: &Path, dir: &Path) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let file = std::fs::File::open(path).map_err(|e| format!("Could not open {}: {}", path.display(), e))?;
+    zip::ZipArchive::new(file)?.extract(dir)?;
+
+    if dir.join("manifest.json").exists() {
+        return Ok(dir.to_path_buf());
+    }
+
+    let entries: Vec<PathBuf> = std::fs::read_dir(dir)?.flatten().map(|e| e.path()).collect();
+    match entries.as_slice() {
+        [single] if single.is_dir() => Ok(single.clone()),
+        _ => Ok(dir.to_path_buf()),
+    }
+}
+
+fn copy_dir(from: &Path, to: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            std::fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Dotted numeric versions like "1.2" or "0.3.1"
+fn parse_version(version: &str) -> Option<Vec<u64>> {
+    version.trim().split('.').map(|part| part.parse().ok()).collect()
+}
+
+/// Compare versions, treating missing parts as 0 and bad versions as oldest
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    match (parse_version(a), parse_version(b)) {
+        (Some(a), Some(b)) => {
+            let len = a.len().max(b.len());
+            let part = |v: &[u64], i: usize| v.get(i).copied().unwrap_or(0);
+            (0..len)
+                .map(|i| part(&a, i).cmp(&part(&b, i)))
+                .find(|o| o.is_ne())
+                .unwrap_or(Ordering::Equal)
+        }
+        (a, b) => a.is_some().cmp(&b.is_some()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_bundle(dir: &Path, version: &str) {
+        std::fs::create_dir_all(dir).unwrap();
+        std::fs::write(dir.join("config.json"), r#"{ "game": "Test Game" }"#).unwrap();
+        std::fs::write(
+            dir.join("splits.json"),
+            r#"{ "game": "Test Game", "category": "Any%", "splits": [{ "name": "End" }] }"#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("manifest.json"),
+            format!(r#"{{ "id": "test-game", "version": "{}", "author": "Tester" }}"#, version),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_compare_versions() {
+        assert_eq!(compare_versions("1.10", "1.9"), Ordering::Greater);
+        assert_eq!(compare_versions("1.0", "1"), Ordering::Equal);
+        assert_eq!(compare_versions("0.2.1", "0.3"), Ordering::Less);
+        assert_eq!(compare_versions("beta", "0.1"), Ordering::Less);
+    }
+
+    #[test]
+    fn test_install_keeps_newer_version() {
+        let root = std::env::temp_dir().join(format!("macsplit-install-test-{}", std::process::id()));
+        let user_dir = root.join("installed");
+        write_bundle(&root.join("v2"), "2.0");
+        write_bundle(&root.join("v1"), "1.5");
+
+        let target = install_bundle(&root.join("v2"), &user_dir).unwrap();
+        assert_eq!(target, user_dir.join("test-game"));
+        assert!(install_bundle(&root.join("v1"), &user_dir).is_err());
+        assert_eq!(AvailableGame::load(&target).unwrap().version(), "2.0");
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_discovery_reports_broken_bundles() {
+        let root = std::env::temp_dir().join(format!("macsplit-discover-{}", std::process::id()));
+        write_bundle(&root.join("good"), "1.0");
+        write_bundle(&root.join("bad-manifest"), "1.0");
+        std::fs::write(root.join("bad-manifest/manifest.json"), "{ \"id\": ").unwrap();
+        write_bundle(&root.join("bad-config"), "1.0");
+        std::fs::write(root.join("bad-config/config.json"), "{}").unwrap();
+        std::fs::create_dir_all(root.join("not-an-autosplitter")).unwrap();
+
+        let found = discover_in(std::slice::from_ref(&root));
+        assert_eq!(found.games.len(), 1);
+        assert_eq!(found.games[0].dir, root.join("good"));
+
+        let broken: Vec<_> = found.broken.iter().map(|b| b.dir.clone()).collect();
+        assert_eq!(broken, vec![root.join("bad-config"), root.join("bad-manifest")]);
+        assert!(found.broken[0].error.contains("config.json"));
+        assert!(found.broken[1].error.contains("manifest.json"));
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_load_validates_bundle() {
+        let dir = std::env::temp_dir().join(format!("macsplit-bundle-{}", std::process::id()));
+        write_bundle(&dir, "1.2.0");
+
+        let game = AvailableGame::load(&dir).unwrap();
+        assert_eq!(game.folder_name, "test-game");
+        assert_eq!(game.version(), "1.2.0");
+
+        std::fs::write(
+            dir.join("manifest.json"),
+            r#"{ "id": "test-game", "version": "1.0", "min_macsplit_version": "999.0" }"#,
+        )
+        .unwrap();
+        assert!(AvailableGame::load(&dir).is_err());
+
+        std::fs::remove_file(dir.join("splits.json")).unwrap();
+        assert!(AvailableGame::load(&dir).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,145 @@
+use livesplit_core::{Run, Segment, Time, TimeSpan, Timer, TimerPhase, TimingMethod};
+
+/// Practice runs have no personal best of their own, so they are timed
+/// against the golds
+const BEST_SEGMENTS: &str = "Best Segments";
+
+/// Practicing splits `first..=last` on their own. The timer runs just those
+/// segments while the full run is kept aside; practice times go into its
+/// segment history and golds, never its personal best or attempts.
+pub struct Practice {
+    pub first: usize,
+    pub last: usize,
+    full: Run,
+    comparison: String,
+}
+
+impl Practice {
+    /// Swap the timer, which must not be running, for one over the practiced
+    /// segments
+    pub fn start(timer: &mut Timer, first: usize, last: usize) -> Result<Self, Box<dyn std::error::Error>> {
+        let full = timer.run();
+        let segments = full.segments().get(first..=last).ok_or("No such splits to practice")?;
+
+        let mut run = Run::new();
+        run.set_game_name(full.game_name());
+        run.set_category_name(format!("{} (practice)", full.category_name()));
+        for segment in segments {
+            let mut practiced = Segment::new(segment.name());
+            practiced.set_best_segment_time(segment.best_segment_time());
+            run.push_segment(practiced);
+        }
+
+        let mut practice_timer = Timer::new(run).map_err(|_| "Failed to create timer")?;
+        practice_timer.set_current_timing_method(timer.current_timing_method());
+        let _ = practice_timer.set_current_comparison(BEST_SEGMENTS);
+
+        let comparison = timer.current_comparison().to_string();
+        let full = std::mem::replace(timer, practice_timer).into_run(false);
+        Ok(Self { first, last, full, comparison })
+    }
+
+    /// Put the full run back on the timer
+    pub fn stop(self, timer: &mut Timer) {
+        let mut full = Timer::new(self.full).expect("the full run has splits");
+        full.set_current_timing_method(timer.current_timing_method());
+        let _ = full.set_current_comparison(self.comparison.as_str());
+        *timer = full;
+    }
+
+    /// The run being practiced, with the practice history so far
+    pub fn full_run(&self) -> &Run {
+        &self.full
+    }
+
+    /// The split of the full run the watchers should be at. Before the
+    /// practice starts that is the split ending the previous segment, whose
+    /// trigger starts the timer.
+    pub fn split_index(&self, timer: &Timer) -> usize {
+        match timer.current_split_index() {
+            Some(index) if timer.current_phase() != TimerPhase::NotRunning => self.first + index,
+            _ => self.first.saturating_sub(1),
+        }
+    }
+
+    /// Whether a split of the full run starts the practice
+    pub fn starts_on_split(&self, index: usize) -> bool {
+        self.first > 0 && index + 1 == self.first
+    }
+
+    /// Keep the segments of a practice attempt in the full run's segment
+    /// history, taking any new golds along. Entries get indexes below every
+    /// attempt's, like history that isn't part of an attempt.
+    pub fn record(&mut self, timer: &Timer) {
+        let index = self
+            .full
+            .segments()
+            .iter()
+            .flat_map(|s| s.segment_history().iter().map(|&(index, _)| index))
+            .min()
+            .unwrap_or(1)
+            .min(1)
+            - 1;
+
+        let minus = |a: Option<TimeSpan>, b: Option<TimeSpan>| Some(a? - b?);
+        let mut previous = Time::new()
+            .with_real_time(Some(TimeSpan::zero()))
+            .with_game_time(Some(TimeSpan::zero()));
+
+        for (i, segment) in timer.run().segments().iter().enumerate() {
+            let split = segment.split_time();
+            if split.real_time.is_none() && split.game_time.is_none() {
+                continue; // Skipped or not reached
+            }
+            let time = Time::new()
+                .with_real_time(minus(split.real_time, previous.real_time))
+                .with_game_time(minus(split.game_time, previous.game_time));
+            previous = split;
+
+            let full_segment = self.full.segment_mut(self.first + i);
+            full_segment.segment_history_mut().insert(index, time);
+            let best = full_segment.best_segment_time_mut();
+            for method in [TimingMethod::RealTime, TimingMethod::GameTime] {
+                if let Some(t) = time[method] {
+                    if best[method].is_none_or(|b| t < b) {
+                        best[method] = Some(t);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_practice_only_adds_segment_history() {
+        let mut run = Run::new();
+        for name in ["Stage 1", "Stage 2", "Stage 3"] {
+            run.push_segment(Segment::new(name));
+        }
+        let mut timer = Timer::new(run).unwrap();
+
+        let mut practice = Practice::start(&mut timer, 1, 2).unwrap();
+        assert_eq!(timer.run().len(), 2);
+        // Stage 1's split starts the practice
+        assert!(practice.starts_on_split(0));
+        assert_eq!(practice.split_index(&timer), 0);
+
+        timer.start();
+        assert_eq!(practice.split_index(&timer), 1);
+        timer.split();
+        practice.record(&timer);
+        timer.reset(false);
+        practice.stop(&mut timer);
+
+        let run = timer.run();
+        assert_eq!(run.len(), 3);
+        assert_eq!(run.attempt_count(), 0);
+        assert_eq!(run.segment(1).segment_history().iter().count(), 1);
+        assert!(run.segment(1).best_segment_time().real_time.is_some());
+        assert_eq!(run.segment(2).segment_history().iter().count(), 0);
+    }
+}
@@ -0,0 +1,149 @@
+use crate::theme::Rgb;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Text in one color
+#[derive(Debug, Clone, PartialEq)]
+pub struct Span {
+    pub text: String,
+    pub color: Rgb,
+}
+
+/// One row of the terminal
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Line {
+    pub spans: Vec<Span>,
+}
+
+impl Line {
+    pub fn span(&mut self, color: Rgb, text: impl Into<String>) -> &mut Self {
+        self.spans.push(Span { text: text.into(), color });
+        self
+    }
+
+    /// The line cut to `width` columns
+    pub fn cut(&self, width: usize) -> Line {
+        let mut left = width;
+        let spans = self
+            .spans
+            .iter()
+            .map_while(|span| {
+                if left == 0 {
+                    return None;
+                }
+                let text = take_width(&span.text, left);
+                left -= text.width();
+                Some(Span { text, color: span.color })
+            })
+            .collect();
+        Line { spans }
+    }
+}
+
+/// Everything on screen, line by line. Drawing compares a frame with the
+/// previous one and only rewrites the lines that changed, so nothing flickers.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Frame {
+    pub width: usize,
+    pub height: usize,
+    pub lines: Vec<Line>,
+}
+
+impl Frame {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self { width, height, lines: Vec::new() }
+    }
+
+    /// Add a line to fill in
+    pub fn line(&mut self) -> &mut Line {
+        self.lines.push(Line::default());
+        self.lines.last_mut().unwrap()
+    }
+
+    pub fn blank(&mut self) {
+        self.lines.push(Line::default());
+    }
+
+    /// Rows to redraw after `previous`, including rows it used that are now
+    /// empty. None when the terminal was resized and everything is redrawn.
+    pub fn changed_rows(&self, previous: Option<&Frame>) -> Option<Vec<usize>> {
+        let previous = previous.filter(|p| (p.width, p.height) == (self.width, self.height))?;
+        let rows = self.lines.len().max(previous.lines.len()).min(self.height);
+        Some((0..rows).filter(|&row| self.lines.get(row) != previous.lines.get(row)).collect())
+    }
+}
+
+/// `text` cut or padded to exactly `width` columns, ending in "…" when cut
+pub fn fit(text: &str, width: usize) -> String {
+    let mut fitted = if text.width() <= width {
+        text.to_string()
+    } else if width == 0 {
+        String::new()
+    } else {
+        take_width(text, width - 1) + "…"
+    };
+    // Wide characters may not fill the width exactly
+    let padding = width - fitted.width();
+    fitted.push_str(&" ".repeat(padding));
+    fitted
+}
+
+/// The start of `text` that fits in `width` columns. Terminals draw some
+/// characters, like CJK or emoji, two columns wide.
+fn take_width(text: &str, width: usize) -> String {
+    let mut used = 0;
+    text.chars()
+        .take_while(|c| {
+            used += c.width().unwrap_or(0);
+            used <= width
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WHITE: Rgb = Rgb::new(255, 255, 255);
+
+    #[test]
+    fn test_fit() {
+        assert_eq!(fit("Mom", 5), "Mom  ");
+        assert_eq!(fit("The Chest", 6), "The C…");
+        assert_eq!(fit("Mom", 0), "");
+        // Two columns per character
+        assert_eq!(fit("東京タワー", 5), "東京…");
+        assert_eq!(fit("東京タワー", 6), "東京… ");
+    }
+
+    #[test]
+    fn test_lines_are_cut_across_spans() {
+        let mut frame = Frame::new(6, 1);
+        frame.line().span(WHITE, " 1:23").span(WHITE, ".456");
+        let line = frame.lines[0].cut(frame.width);
+
+        assert_eq!(line.spans.len(), 2);
+        assert_eq!(line.spans[1].text, ".");
+
+        let mut line = Line::default();
+        line.span(WHITE, " 東京");
+        assert_eq!(line.cut(3).spans[0].text, " 東");
+        assert_eq!(line.cut(2).spans[0].text, " ");
+    }
+
+    #[test]
+    fn test_only_changed_rows_are_redrawn() {
+        let mut previous = Frame::new(40, 10);
+        previous.line().span(WHITE, "Game");
+        previous.line().span(WHITE, "0:01.000");
+        previous.line().span(WHITE, "[Q] Quit");
+
+        let mut frame = Frame::new(40, 10);
+        frame.line().span(WHITE, "Game");
+        frame.line().span(WHITE, "0:01.016");
+
+        // The timer changed and the controls row has to be cleared
+        assert_eq!(frame.changed_rows(Some(&previous)), Some(vec![1, 2]));
+        assert_eq!(frame.changed_rows(None), None);
+        assert_eq!(Frame::new(30, 10).changed_rows(Some(&previous)), None);
+    }
+}
@@ -0,0 +1,435 @@
+use crate::splits::SplitsFile;
+use crate::watcher::Ending;
+use rhai::module_resolvers::DummyModuleResolver;
+use rhai::{CallFnOptions, Dynamic, Engine, Scope, AST};
+use std::path::Path;
+
+pub const SCRIPT_FILE: &str = "autosplitter.rhai";
+
+/// Limits that keep a misbehaving script from hanging the timer
+const MAX_OPERATIONS: u64 = 100_000;
+const MAX_CALL_LEVELS: usize = 32;
+const MAX_STRING_SIZE: usize = 64 * 1024;
+const MAX_ARRAY_SIZE: usize = 10_000;
+const MAX_MAP_SIZE: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Start,
+    Split,
+    Reset,
+    Pause,
+    Resume,
+    Skip,
+    Death,
+    Quit,
+    NewRun,
+}
+
+impl Action {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "start" => Some(Action::Start),
+            "split" => Some(Action::Split),
+            "reset" => Some(Action::Reset),
+            "pause" => Some(Action::Pause),
+            "resume" => Some(Action::Resume),
+            "skip" => Some(Action::Skip),
+            "death" => Some(Action::Death),
+            "quit" => Some(Action::Quit),
+            "new_run" => Some(Action::NewRun),
+            _ => None,
+        }
+    }
+}
+
+/// An autosplitter written in Rhai. The script defines
+/// `fn on_line(line, split_index)`, which is called for every new log line
+/// and returns an action name, an array of action names, or `()`.
+/// State persists between calls on `this`, an object map; entries of
+/// `this.info` are shown as info rows.
+///
+/// Scripts run sandboxed: no module imports, no `eval`, no printing and a
+/// bounded number of operations per call. Rhai has no filesystem or network
+/// access of its own.
+pub struct ScriptAutosplitter {
+    engine: Engine,
+    ast: AST,
+    scope: Scope<'static>,
+    state: Dynamic,
+}
+
+impl ScriptAutosplitter {
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let source = std::fs::read_to_string(path)?;
+        Self::compile(&source)
+    }
+
+    /// Load `autosplitter.rhai` from a game folder, if it has one, with the
+    /// splits file's characters, script settings and ending policies
+    /// available as `this.characters`, `this.settings` and `this.endings`.
+    pub fn load_for_dir(
+        dir: &Path,
+        splits_file: &SplitsFile,
+    ) -> Result<Option<Self>, Box<dyn std::error::Error>> {
+        let path = dir.join(SCRIPT_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let mut script = Self::load(&path)?;
+        script.set_state("characters", rhai::serde::to_dynamic(&splits_file.characters)?)?;
+        script.set_state("settings", rhai::serde::to_dynamic(&splits_file.script_settings)?)?;
+        let endings = serde_json::json!({
+            "death": splits_file.endings.policy(Ending::Death),
+            "quit": splits_file.endings.policy(Ending::Quit),
+            "new_run": splits_file.endings.policy(Ending::NewRun),
+        });
+        script.set_state("endings", rhai::serde::to_dynamic(&endings)?)?;
+        Ok(Some(script))
+    }
+
+    fn set_state(&mut self, key: &str, value: Dynamic) -> Result<(), Box<dyn std::error::Error>> {
+        let mut state = self
+            .state
+            .write_lock::<rhai::Map>()
+            .ok_or("Script state is not an object map")?;
+        state.insert(key.into(), value);
+        Ok(())
+    }
+
+    pub fn compile(source: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut engine = Engine::new();
+        engine.set_module_resolver(DummyModuleResolver::new());
+        engine.disable_symbol("eval");
+        engine.on_print(|_| {});
+        engine.on_debug(|_, _, _| {});
+        engine.set_max_operations(MAX_OPERATIONS);
+        engine.set_max_call_levels(MAX_CALL_LEVELS);
+        engine.set_max_string_size(MAX_STRING_SIZE);
+        engine.set_max_array_size(MAX_ARRAY_SIZE);
+        engine.set_max_map_size(MAX_MAP_SIZE);
+
+        let ast = engine.compile(source)?;
+
+        // Run top-level statements once so bad imports fail at load time
+        let mut scope = Scope::new();
+        engine.run_ast_with_scope(&mut scope, &ast)?;
+
+        Ok(Self {
+            engine,
+            ast,
+            scope,
+            state: Dynamic::from(rhai::Map::new()),
+        })
+    }
+
+    pub fn on_line(
+        &mut self,
+        line: &str,
+        split_index: usize,
+    ) -> Result<Vec<Action>, Box<dyn std::error::Error>> {
+        let options = CallFnOptions::new()
+            .bind_this_ptr(&mut self.state)
+            .eval_ast(false);
+
+        let result: Dynamic = self.engine.call_fn_with_options(
+            options,
+            &mut self.scope,
+            &self.ast,
+            "on_line",
+            (line.to_string(), split_index as i64),
+        )?;
+
+        let names = if result.is_unit() {
+            Vec::new()
+        } else if result.is_array() {
+            result
+                .into_array()?
+                .into_iter()
+                .map(|v| v.into_string())
+                .collect::<Result<Vec<_>, _>>()?
+        } else {
+            vec![result.into_string()?]
+        };
+
+        names
+            .iter()
+            .map(|name| {
+                Action::parse(name).ok_or_else(|| format!("Unknown autosplitter action: {}", name).into())
+            })
+            .collect()
+    }
+
+    /// The script's `this.info` entries, for display
+    pub fn info_rows(&self) -> Vec<(String, String)> {
+        let Some(state) = self.state.read_lock::<rhai::Map>() else {
+            return Vec::new();
+        };
+        let Some(info) = state.get("info").and_then(|i| i.read_lock::<rhai::Map>()) else {
+            return Vec::new();
+        };
+
+        info.iter()
+            .map(|(label, value)| (label.to_string(), value.to_string()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COUNTER_SCRIPT: &str = r#"
+        fn on_line(line, split_index) {
+            if !("floors" in this) { this.floors = 0; }
+
+            if line.contains("Start Room") { return "start"; }
+            if line.contains("m_Stage") {
+                this.floors += 1;
+                if this.floors % 2 == 0 { return "split"; }
+            }
+            if line.contains("Game Over") { return ["pause", "reset"]; }
+        }
+    "#;
+
+    #[test]
+    fn test_script_actions_and_state() {
+        let mut script = ScriptAutosplitter::compile(COUNTER_SCRIPT).unwrap();
+
+        assert_eq!(script.on_line("Start Room", 0).unwrap(), vec![Action::Start]);
+        assert!(script.on_line("m_Stage 1", 0).unwrap().is_empty());
+        assert_eq!(script.on_line("m_Stage 2", 0).unwrap(), vec![Action::Split]);
+        assert_eq!(
+            script.on_line("Game Over", 1).unwrap(),
+            vec![Action::Pause, Action::Reset]
+        );
+        assert!(script.on_line("unrelated", 1).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_script_is_sandboxed() {
+        let mut script = ScriptAutosplitter::compile(
+            r#"fn on_line(line, split_index) { loop { } }"#,
+        ).unwrap();
+        assert!(script.on_line("anything", 0).is_err());
+
+        assert!(ScriptAutosplitter::compile(r#"import "other" as other;"#).is_err());
+    }
+
+    #[test]
+    fn test_oversized_array_is_rejected() {
+        let mut script = ScriptAutosplitter::compile(
+            r#"fn on_line(line, split_index) { let floors = []; floors.pad(20000, 0); [] }"#,
+        ).unwrap();
+        let error = script.on_line("anything", 0).unwrap_err();
+        assert!(error.to_string().contains("array"), "{error}");
+    }
+
+    #[test]
+    fn test_unknown_action_is_an_error() {
+        let mut script = ScriptAutosplitter::compile(
+            r#"fn on_line(line, split_index) { "explode" }"#,
+        ).unwrap();
+        assert!(script.on_line("anything", 0).is_err());
+    }
+
+    #[test]
+    fn test_isaac_script_against_fixture_log() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("autosplitters/binding-of-isaac-rebirth");
+        let mut script = ScriptAutosplitter::load_for_dir(&dir, &SplitsFile::default_run()).unwrap().unwrap();
+        let log = std::fs::read_to_string(dir.join("test.log")).unwrap();
+
+        let mut actions = Vec::new();
+        for line in log.lines() {
+            actions.extend(script.on_line(line, 0).unwrap());
+        }
+
+        // First run: start on Basement I, split into Cellar II (the repeated
+        // Level::Init is ignored) and Catacombs I, then a new seed abandons it.
+        // Second run: start, then one split per floor left, with Womb I
+        // counting once as a Labyrinth floor before Cathedral and Chest.
+        let mut expected = vec![Action::Start, Action::Split, Action::Split, Action::NewRun, Action::Start];
+        expected.extend([Action::Split; 8]);
+        assert_eq!(actions, expected);
+
+        assert_eq!(
+            script.info_rows(),
+            vec![
+                ("Character".to_string(), "Isaac".to_string()),
+                ("Floor".to_string(), "Chest".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_isaac_floor_names() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("autosplitters/binding-of-isaac-rebirth");
+        let mut script = ScriptAutosplitter::load_for_dir(&dir, &SplitsFile::default_run()).unwrap().unwrap();
+
+        let floors = [
+            ("m_Stage 2, m_AltStage 1", "Cellar II"),
+            ("m_Stage 3, m_AltStage 1", "Catacombs I"),
+            ("m_Stage 6, m_AltStage 1", "Necropolis II"),
+            ("m_Stage 6, m_StageType 2, m_AltStage 0", "Dank Depths II"),
+            ("m_Stage 10, m_AltStage 0", "Sheol"),
+            ("m_Stage 10, m_AltStage 1", "Cathedral"),
+            ("m_Stage 11, m_AltStage 0", "Dark Room"),
+        ];
+        for (stage, floor) in floors {
+            script.on_line(&format!("[INFO] - Level::Init {} Seed 1", stage), 0).unwrap();
+            assert_eq!(script.info_rows(), vec![("Floor".to_string(), floor.to_string())]);
+        }
+    }
+
+    #[test]
+    fn test_isaac_script_chapters_only() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("autosplitters/binding-of-isaac-rebirth");
+        let mut splits_file = SplitsFile::default_run();
+        splits_file.script_settings.insert("every_floor".to_string(), false.into());
+        let mut script = ScriptAutosplitter::load_for_dir(&dir, &splits_file).unwrap().unwrap();
+
+        let mut actions = script.on_line("RNG Start Seed: AAAA AAAA (1)", 0).unwrap();
+        for stage in 1..=4 {
+            actions.extend(script.on_line(&format!("Level::Init m_Stage {}, m_AltStage 0", stage), 0).unwrap());
+        }
+
+        // Leaving Basement I and Caves I doesn't split, leaving Basement II does
+        assert_eq!(actions, vec![Action::Start, Action::Split]);
+    }
+
+    #[test]
+    fn test_isaac_script_labyrinth_floors() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("autosplitters/binding-of-isaac-rebirth");
+        let run = |every_floor: bool| {
+            let mut splits_file = SplitsFile::default_run();
+            splits_file.script_settings.insert("every_floor".to_string(), every_floor.into());
+            let mut script = ScriptAutosplitter::load_for_dir(&dir, &splits_file).unwrap().unwrap();
+
+            // Basement XL, Caves and Depths I and II, then Womb XL
+            let mut actions = script.on_line("RNG Start Seed: AAAA AAAA (1)", 0).unwrap();
+            for stage in [1, 3, 4, 5, 6, 7, 9] {
+                actions.extend(script.on_line(&format!("Level::Init m_Stage {}, m_AltStage 0", stage), 0).unwrap());
+            }
+            actions
+        };
+
+        // B1 is skipped and B2 split when Basement XL is left; the Womb is one split
+        let mut expected = vec![Action::Start, Action::Skip];
+        expected.extend([Action::Split; 6]);
+        assert_eq!(run(true), expected);
+        // Chapters only: Basement XL ends the chapter, Caves I doesn't
+        assert_eq!(run(false), vec![Action::Start, Action::Split, Action::Split, Action::Split, Action::Split]);
+    }
+
+    #[test]
+    fn test_isaac_script_win_and_death_lines() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("autosplitters/binding-of-isaac-rebirth");
+        let mut splits_file = SplitsFile::default_run();
+        splits_file.script_settings = serde_json::json!({
+            "win_line": "Lua Debug: Run won",
+            "death_line": "Lua Debug: Run lost",
+        })
+        .as_object()
+        .unwrap()
+        .clone();
+        let mut script = ScriptAutosplitter::load_for_dir(&dir, &splits_file).unwrap().unwrap();
+
+        let mut actions = Vec::new();
+        for line in [
+            "[INFO] - RNG Start Seed: AAAA AAAA (1)",
+            "[INFO] - Level::Init m_Stage 1, m_AltStage 0 Seed 1",
+            "[INFO] - Lua Debug: Run lost",
+            "[INFO] - RNG Start Seed: BBBB BBBB (2)",
+            "[INFO] - Level::Init m_Stage 1, m_AltStage 0 Seed 2",
+            "[INFO] - Level::Init m_Stage 2, m_AltStage 0 Seed 3",
+            "[INFO] - Lua Debug: Run won",
+            // Not in a run any more
+            "[INFO] - Lua Debug: Run lost",
+            "[INFO] - RNG Start Seed: CCCC CCCC (3)",
+        ] {
+            actions.extend(script.on_line(line, 0).unwrap());
+        }
+
+        // The death ends the first attempt, the win splits the final split
+        // and the next seed resets the finished one
+        assert_eq!(
+            actions,
+            vec![Action::Start, Action::Death, Action::Start, Action::Split, Action::Split, Action::Reset]
+        );
+    }
+
+    #[test]
+    fn test_isaac_script_multi_character_streak() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("autosplitters/binding-of-isaac-rebirth");
+        let mut splits_file = SplitsFile::default_run();
+        splits_file.characters = vec!["Isaac".to_string(), "Cain".to_string()];
+        splits_file.script_settings = serde_json::json!({
+            "win_line": "Run Won",
+            "death_line": "Run Lost",
+        })
+        .as_object()
+        .unwrap()
+        .clone();
+        let mut script = ScriptAutosplitter::load_for_dir(&dir, &splits_file).unwrap().unwrap();
+
+        fn run(script: &mut ScriptAutosplitter, lines: &[&str]) -> Vec<Action> {
+            lines.iter().flat_map(|l| script.on_line(l, 0).unwrap()).collect()
+        }
+
+        // Isaac: start, one floor, win closes the character's group
+        assert_eq!(
+            run(&mut script, &["RNG Start Seed: AAAA AAAA (1)", "Initialized player with Variant 0 and Subtype 0",
+                  "Level::Init m_Stage 1, m_AltStage 0", "Level::Init m_Stage 2, m_AltStage 0", "Run Won"]),
+            vec![Action::Start, Action::Split, Action::Split]
+        );
+        // Cain: the new seed continues the streak instead of resetting
+        assert_eq!(
+            run(&mut script, &["RNG Start Seed: BBBB BBBB (2)", "Initialized player with Variant 0 and Subtype 2",
+                  "Level::Init m_Stage 1, m_AltStage 0", "Level::Init m_Stage 2, m_AltStage 0"]),
+            vec![Action::Split]
+        );
+        assert!(script.info_rows().contains(&("Character".to_string(), "Cain".to_string())));
+        // Abandoning Cain ends the attempt; the next run starts a new one
+        assert_eq!(
+            run(&mut script, &["RNG Start Seed: CCCC CCCC (3)", "Level::Init m_Stage 1, m_AltStage 0"]),
+            vec![Action::NewRun, Action::Start]
+        );
+        assert_eq!(run(&mut script, &["Run Lost"]), vec![Action::Death]);
+    }
+
+    #[test]
+    fn test_isaac_script_streak_continues_through_deaths() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("autosplitters/binding-of-isaac-rebirth");
+        let mut splits_file = SplitsFile::default_run();
+        splits_file.characters = vec!["Isaac".to_string(), "Cain".to_string()];
+        splits_file.script_settings = serde_json::json!({ "win_line": "Run Won", "death_line": "Run Lost" })
+            .as_object()
+            .unwrap()
+            .clone();
+        splits_file.endings = serde_json::from_value(serde_json::json!({ "death": { "policy": "continue" } })).unwrap();
+        let mut script = ScriptAutosplitter::load_for_dir(&dir, &splits_file).unwrap().unwrap();
+
+        fn run(script: &mut ScriptAutosplitter, lines: &[&str]) -> Vec<Action> {
+            lines.iter().flat_map(|l| script.on_line(l, 0).unwrap()).collect()
+        }
+
+        assert_eq!(
+            run(&mut script, &["RNG Start Seed: AAAA AAAA (1)", "Level::Init m_Stage 1, m_AltStage 0",
+                  "Level::Init m_Stage 2, m_AltStage 0", "Run Won"]),
+            vec![Action::Start, Action::Split, Action::Split]
+        );
+        // Cain dies and tries again within the same attempt
+        assert_eq!(
+            run(&mut script, &["RNG Start Seed: BBBB BBBB (2)", "Level::Init m_Stage 1, m_AltStage 0", "Run Lost"]),
+            vec![Action::Death]
+        );
+        assert_eq!(
+            run(&mut script, &["RNG Start Seed: CCCC CCCC (3)", "Level::Init m_Stage 1, m_AltStage 0",
+                  "Level::Init m_Stage 2, m_AltStage 0", "Run Won"]),
+            vec![Action::Split, Action::Split]
+        );
+        // Cain's win was the last one, so the next seed resets the finished run
+        assert_eq!(run(&mut script, &["RNG Start Seed: DDDD DDDD (4)"]), vec![Action::Reset]);
+    }
+}
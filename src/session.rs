@@ -0,0 +1,192 @@
+use crate::suggest::TimedLine;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::time::Instant;
+
+/// A timer action taken by the runner
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ManualAction {
+    Start,
+    Split,
+    Undo,
+    Skip,
+    Reset,
+    Pause,
+    Resume,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SessionEvent {
+    Splits { game: String, category: String }, // The splits in use from here on
+    Log { line: String },
+    Action { action: ManualAction },
+}
+
+/// One line of a session file: an event and when it happened, in seconds
+/// since recording started
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionEntry {
+    pub t: f64,
+    #[serde(flatten)]
+    pub event: SessionEvent,
+}
+
+/// Writes the raw log lines and the runner's timer actions of a session to a
+/// JSON lines file, in the order they happened.
+pub struct SessionRecorder {
+    file: File,
+    started: Instant,
+}
+
+impl SessionRecorder {
+    pub fn create(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let file = File::create(path)
+            .map_err(|e| format!("Could not create session file {}: {}", path.display(), e))?;
+        Ok(Self { file, started: Instant::now() })
+    }
+
+    pub fn splits(&mut self, game: &str, category: &str) {
+        self.write(SessionEvent::Splits {
+            game: game.to_string(),
+            category: category.to_string(),
+        });
+    }
+
+    pub fn log_line(&mut self, line: &str) {
+        self.write(SessionEvent::Log { line: line.to_string() });
+    }
+
+    pub fn action(&mut self, action: ManualAction) {
+        self.write(SessionEvent::Action { action });
+    }
+
+    fn write(&mut self, event: SessionEvent) {
+        let entry = SessionEntry {
+            t: self.started.elapsed().as_secs_f64(),
+            event,
+        };
+        // Written line by line so a crash keeps everything up to it
+        if let Ok(json) = serde_json::to_string(&entry) {
+            let _ = writeln!(self.file, "{}", json);
+        }
+    }
+}
+
+/// One attempt in a session, from a start to the next reset
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Attempt {
+    pub start: Option<f64>,
+    pub splits: Vec<Option<f64>>, // Split times, None for skipped splits
+}
+
+pub struct Session {
+    pub entries: Vec<SessionEntry>,
+}
+
+impl Session {
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let reader = BufReader::new(File::open(path)?);
+        let entries = reader
+            .lines()
+            .enumerate()
+            .filter(|(_, l)| l.as_ref().map(|l| !l.trim().is_empty()).unwrap_or(true))
+            .map(|(i, l)| -> Result<SessionEntry, Box<dyn std::error::Error>> {
+                let l = l?;
+                serde_json::from_str(&l).map_err(|e| format!("Line {}: {}", i + 1, e).into())
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { entries })
+    }
+
+    pub fn timed_log(&self) -> Vec<TimedLine> {
+        self.entries
+            .iter()
+            .filter_map(|e| match e.event {
+                SessionEvent::Log { ref line } => Some(TimedLine { time: e.t, line: line.clone() }),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The attempts the runner started, replaying undos and resets
+    pub fn attempts(&self) -> Vec<Attempt> {
+        let mut attempts = Vec::new();
+        let mut current: Option<Attempt> = None;
+
+        for entry in &self.entries {
+            let SessionEvent::Action { action } = entry.event else {
+                continue;
+            };
+            match (action, current.as_mut()) {
+                (ManualAction::Start, None) => {
+                    current = Some(Attempt { start: Some(entry.t), splits: Vec::new() });
+                }
+                (ManualAction::Split, Some(attempt)) => attempt.splits.push(Some(entry.t)),
+                (ManualAction::Skip, Some(attempt)) => attempt.splits.push(None),
+                (ManualAction::Undo, Some(attempt)) => {
+                    attempt.splits.pop();
+                }
+                (ManualAction::Reset, Some(_)) => attempts.extend(current.take()),
+                _ => {}
+            }
+        }
+
+        attempts.extend(current);
+        attempts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(t: f64, event: SessionEvent) -> SessionEntry {
+        SessionEntry { t, event }
+    }
+
+    fn action(t: f64, action: ManualAction) -> SessionEntry {
+        entry(t, SessionEvent::Action { action })
+    }
+
+    #[test]
+    fn test_entries_round_trip_as_json_lines() {
+        let log = entry(1.5, SessionEvent::Log { line: "Level::Init m_Stage 2".to_string() });
+        let json = serde_json::to_string(&log).unwrap();
+        assert_eq!(json, r#"{"t":1.5,"kind":"log","line":"Level::Init m_Stage 2"}"#);
+        assert_eq!(serde_json::from_str::<SessionEntry>(&json).unwrap(), log);
+
+        let split = serde_json::to_string(&action(2.0, ManualAction::Split)).unwrap();
+        assert_eq!(split, r#"{"t":2.0,"kind":"action","action":"split"}"#);
+    }
+
+    #[test]
+    fn test_attempts_replay_undo_skip_and_reset() {
+        let session = Session {
+            entries: vec![
+                action(1.0, ManualAction::Start),
+                action(5.0, ManualAction::Split),
+                action(6.0, ManualAction::Split),
+                action(7.0, ManualAction::Undo),
+                action(9.0, ManualAction::Skip),
+                action(12.0, ManualAction::Split),
+                action(13.0, ManualAction::Reset),
+                action(20.0, ManualAction::Start),
+                entry(21.0, SessionEvent::Log { line: "Boss".to_string() }),
+                action(22.0, ManualAction::Split),
+            ],
+        };
+
+        assert_eq!(
+            session.attempts(),
+            vec![
+                Attempt { start: Some(1.0), splits: vec![Some(5.0), None, Some(12.0)] },
+                Attempt { start: Some(20.0), splits: vec![Some(22.0)] },
+            ]
+        );
+        assert_eq!(session.timed_log().len(), 1);
+    }
+}
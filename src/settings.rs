@@ -0,0 +1,362 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// A setting an autosplitter folder declares in its `config.json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SettingDefinition {
+    pub key: String,
+    pub label: String,
+    #[serde(flatten)]
+    pub kind: SettingKind,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum SettingKind {
+    Bool {
+        #[serde(default)]
+        default: bool,
+    },
+    Choice {
+        options: Vec<String>,
+        #[serde(default)]
+        default: Option<String>, // First option if unset
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SettingValue {
+    Bool(bool),
+    Choice(String),
+}
+
+impl SettingDefinition {
+    fn default_value(&self) -> SettingValue {
+        match self.kind {
+            SettingKind::Bool { default } => SettingValue::Bool(default),
+            SettingKind::Choice { ref options, ref default } => SettingValue::Choice(
+                default
+                    .clone()
+                    .or_else(|| options.first().cloned())
+                    .unwrap_or_default(),
+            ),
+        }
+    }
+
+    fn accepts(&self, value: &SettingValue) -> bool {
+        match (&self.kind, value) {
+            (SettingKind::Bool { .. }, SettingValue::Bool(_)) => true,
+            (SettingKind::Choice { options, .. }, SettingValue::Choice(v)) => options.contains(v),
+            _ => false,
+        }
+    }
+}
+
+/// Makes a split only active for some settings: a bool setting must be on,
+/// a choice setting must equal `equals`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SettingCondition {
+    pub setting: String,
+    #[serde(default)]
+    pub equals: Option<String>,
+}
+
+/// One game's settings with the user's values, saved per user in the config
+/// directory so they survive updates to the autosplitter folder.
+#[derive(Debug, Clone, Default)]
+pub struct GameSettings {
+    definitions: Vec<SettingDefinition>,
+    values: BTreeMap<String, SettingValue>,
+    path: Option<PathBuf>,
+}
+
+impl GameSettings {
+    pub fn load(folder_name: &str, definitions: Vec<SettingDefinition>) -> Self {
+        let path = dirs_next::config_dir().map(|dir| {
+            dir.join("MacSplit")
+                .join("settings")
+                .join(format!("{}.json", folder_name))
+        });
+
+        let saved: BTreeMap<String, SettingValue> = path
+            .as_ref()
+            .and_then(|p| std::fs::read_to_string(p).ok())
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        Self::with_values(definitions, saved, path)
+    }
+
+    /// Keep the saved values the definitions still accept; default the rest
+    fn with_values(
+        definitions: Vec<SettingDefinition>,
+        saved: BTreeMap<String, SettingValue>,
+        path: Option<PathBuf>,
+    ) -> Self {
+        let values = definitions
+            .iter()
+            .map(|def| {
+                let value = saved
+                    .get(&def.key)
+                    .filter(|v| def.accepts(v))
+                    .cloned()
+                    .unwrap_or_else(|| def.default_value());
+                (def.key.clone(), value)
+            })
+            .collect();
+
+        Self { definitions, values, path }
+    }
+
+    /// The defaults of the settings a `config.json` declares, for splits
+    /// loaded without a game's saved values
+    pub fn defaults_from(config_path: &Path) -> Self {
+        #[derive(Deserialize)]
+        struct Declared {
+            #[serde(default)]
+            settings: Vec<SettingDefinition>,
+        }
+
+        let definitions = std::fs::read_to_string(config_path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<Declared>(&content).ok())
+            .map(|declared| declared.settings)
+            .unwrap_or_default();
+        Self::with_values(definitions, BTreeMap::new(), None)
+    }
+
+    pub fn definitions(&self) -> &[SettingDefinition] {
+        &self.definitions
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.definitions.is_empty()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&SettingValue> {
+        self.values.get(key)
+    }
+
+    /// Change a value and save all values. Returns false for values the
+    /// setting doesn't accept.
+    pub fn set(&mut self, key: &str, value: SettingValue) -> bool {
+        let Some(def) = self.definitions.iter().find(|d| d.key == key) else {
+            return false;
+        };
+        if !def.accepts(&value) {
+            return false;
+        }
+
+        self.values.insert(key.to_string(), value);
+        // Settings still apply for this session if they can't be saved
+        let _ = self.save();
+        true
+    }
+
+    fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let path = self.path.as_ref().ok_or("No config directory")?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(&self.values)?)?;
+        Ok(())
+    }
+
+    /// Check a condition against the setting it names: a bool setting can't
+    /// be compared, and a choice setting must be compared to one of its
+    /// options. Settings that aren't declared are left alone.
+    pub fn check(&self, condition: &SettingCondition) -> Result<(), String> {
+        let Some(def) = self.definitions.iter().find(|d| d.key == condition.setting) else {
+            return Ok(());
+        };
+        match (&def.kind, &condition.equals) {
+            (SettingKind::Bool { .. }, None) => Ok(()),
+            (SettingKind::Bool { .. }, Some(equals)) => Err(format!(
+                "setting {:?} is on or off, it can't equal {:?}",
+                def.key, equals
+            )),
+            (SettingKind::Choice { .. }, None) => {
+                Err(format!("setting {:?} is a choice, `equals` has to pick an option", def.key))
+            }
+            (SettingKind::Choice { options, .. }, Some(equals)) if !options.contains(equals) => Err(format!(
+                "setting {:?} has no option {:?} (options: {})",
+                def.key,
+                equals,
+                options.join(", ")
+            )),
+            (SettingKind::Choice { .. }, Some(_)) => Ok(()),
+        }
+    }
+
+    /// Whether a split with this condition is active. Settings that aren't
+    /// declared don't take any split out of the run.
+    pub fn allows(&self, condition: &SettingCondition) -> bool {
+        match (self.get(&condition.setting), &condition.equals) {
+            (None, _) => true,
+            (Some(SettingValue::Bool(on)), None) => *on,
+            (Some(SettingValue::Choice(v)), Some(equals)) => v == equals,
+            _ => false,
+        }
+    }
+
+    /// Values keyed by setting, for `this.settings` in scripts
+    pub fn to_json(&self) -> serde_json::Map<String, serde_json::Value> {
+        self.values
+            .iter()
+            .map(|(key, value)| {
+                let value = match value {
+                    SettingValue::Bool(b) => serde_json::Value::Bool(*b),
+                    SettingValue::Choice(s) => serde_json::Value::String(s.clone()),
+                };
+                (key.clone(), value)
+            })
+            .collect()
+    }
+}
+
+/// The user's own choices, kept across games in `preferences.json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Preferences {
+    #[serde(default)]
+    pub theme: Option<String>,
+    #[serde(default)]
+    pub visible_splits: Option<usize>, // None for the layout's count, or to fit the terminal
+    #[serde(default = "default_split_preview")]
+    pub split_preview: usize, // Upcoming splits kept in view below the current one
+    #[serde(default)]
+    pub show_graph: bool,
+    #[serde(default)]
+    pub graph_attempts: usize, // Earlier attempts drawn in the graph
+    #[serde(default)]
+    pub window: WindowPreferences,
+}
+
+/// How the GUI window is shown, for streaming and overlaying the game
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowPreferences {
+    pub always_on_top: bool,
+    pub borderless: bool,
+    pub transparent: bool, // No background, to overlay the game or key it out
+    #[serde(skip)]
+    pub click_through: bool, // Never kept: a click-through window can't be clicked to turn it off
+    pub position: Option<[f32; 2]>, // Where the window was last closed
+    pub size: Option<[f32; 2]>,
+}
+
+fn default_split_preview() -> usize {
+    1
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Self {
+            theme: None,
+            visible_splits: None,
+            split_preview: default_split_preview(),
+            show_graph: false,
+            graph_attempts: 0,
+            window: WindowPreferences::default(),
+        }
+    }
+}
+
+impl Preferences {
+    fn path() -> Option<PathBuf> {
+        dirs_next::config_dir().map(|dir| dir.join("MacSplit").join("preferences.json"))
+    }
+
+    pub fn load() -> Self {
+        Self::path()
+            .and_then(|p| std::fs::read_to_string(p).ok())
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Best effort, like game settings
+    pub fn save(&self) {
+        let Some(path) = Self::path() else {
+            return;
+        };
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        if let Ok(json) = serde_json::to_string_pretty(self) {
+            let _ = std::fs::write(path, json);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn definitions() -> Vec<SettingDefinition> {
+        serde_json::from_str(
+            r#"[
+                { "key": "every_floor", "label": "Split on every floor", "type": "bool", "default": true },
+                { "key": "route", "label": "Route", "type": "choice", "options": ["Cathedral", "Sheol"] }
+            ]"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_saved_values_override_defaults() {
+        let saved = serde_json::from_str(r#"{ "every_floor": false, "route": "Mars" }"#).unwrap();
+        let settings = GameSettings::with_values(definitions(), saved, None);
+
+        assert_eq!(settings.get("every_floor"), Some(&SettingValue::Bool(false)));
+        // Not one of the options any more, so back to the first option
+        assert_eq!(settings.get("route"), Some(&SettingValue::Choice("Cathedral".to_string())));
+    }
+
+    #[test]
+    fn test_conditions() {
+        let mut settings = GameSettings::with_values(definitions(), BTreeMap::new(), None);
+        let every_floor = SettingCondition { setting: "every_floor".to_string(), equals: None };
+        let sheol = SettingCondition { setting: "route".to_string(), equals: Some("Sheol".to_string()) };
+
+        assert!(settings.allows(&every_floor));
+        assert!(!settings.allows(&sheol));
+
+        assert!(settings.set("route", SettingValue::Choice("Sheol".to_string())));
+        assert!(!settings.set("route", SettingValue::Bool(true)));
+        assert!(settings.allows(&sheol));
+
+        // Without declared settings nothing is filtered
+        assert!(GameSettings::default().allows(&sheol));
+    }
+
+    #[test]
+    fn test_conditions_must_match_the_setting_type() {
+        let settings = GameSettings::with_values(definitions(), BTreeMap::new(), None);
+        let condition = |setting: &str, equals: Option<&str>| SettingCondition {
+            setting: setting.to_string(),
+            equals: equals.map(str::to_string),
+        };
+
+        assert!(settings.check(&condition("every_floor", None)).is_ok());
+        assert!(settings.check(&condition("route", Some("Sheol"))).is_ok());
+        assert!(settings.check(&condition("undeclared", Some("anything"))).is_ok());
+
+        assert!(settings.check(&condition("every_floor", Some("true"))).is_err());
+        assert!(settings.check(&condition("route", None)).is_err());
+        assert!(settings.check(&condition("route", Some("Mars"))).is_err());
+    }
+
+    #[test]
+    fn test_preferences_fill_in_missing_fields() {
+        let preferences: Preferences =
+            serde_json::from_str(r#"{ "theme": "Light", "window": { "always_on_top": true, "click_through": true } }"#)
+                .unwrap();
+
+        assert_eq!(preferences.split_preview, 1);
+        assert!(preferences.window.always_on_top);
+        // Every session starts with clicks going to the window
+        assert!(!preferences.window.click_through);
+        assert_eq!(preferences.window.position, None);
+    }
+}
@@ -1,5 +1,9 @@
+use crate::history::Policy;
+use crate::settings::{GameSettings, SettingCondition};
+use crate::watcher::{Ending, Guard, Trigger, Variable};
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SplitDefinition {
@@ -8,6 +12,74 @@ pub struct SplitDefinition {
     pub best_time_ms: Option<u64>,
     #[serde(default)]
     pub trigger: Option<String>, // Keyword to watch for in game log
+    #[serde(default)]
+    pub unless: Vec<String>, // Keywords that suppress the trigger on the same line
+    #[serde(default)]
+    pub guards: Vec<Guard>,
+    #[serde(default)]
+    pub when: Option<SettingCondition>, // Only part of the run for these settings
+    #[serde(default)]
+    pub icon: Option<PathBuf>, // PNG, relative to the autosplitter folder
+}
+
+impl SplitDefinition {
+    /// Subsplits are named with a leading "-"
+    pub fn is_subsplit(&self) -> bool {
+        self.name.starts_with('-')
+    }
+
+    pub fn display_name(&self) -> Cow<'_, str> {
+        display_name(&self.name)
+    }
+}
+
+/// A split name as shown: subsplits ("-Floor") without their dash, and the
+/// split closing a group ("{Isaac} Blue Baby") as "Isaac: Blue Baby". The
+/// names are kept as they are in the run, which LiveSplit reads as subsplits.
+pub fn display_name(name: &str) -> Cow<'_, str> {
+    if let Some(name) = name.strip_prefix('-') {
+        return Cow::Borrowed(name);
+    }
+    match name.strip_prefix('{').and_then(|rest| rest.split_once('}')) {
+        Some((group, name)) => Cow::Owned(format!("{}: {}", group, name.trim_start())),
+        None => Cow::Borrowed(name),
+    }
+}
+
+/// How one kind of run ending is detected and what it does to the timer.
+/// Without a `trigger`, only a script can report the ending.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EndingRule {
+    #[serde(default)]
+    pub trigger: Option<String>,
+    #[serde(default)]
+    pub unless: Vec<String>,
+    #[serde(default)]
+    pub policy: Policy,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Endings {
+    #[serde(default)]
+    pub death: Option<EndingRule>,
+    #[serde(default)]
+    pub quit: Option<EndingRule>,
+    #[serde(default)]
+    pub new_run: Option<EndingRule>,
+}
+
+impl Endings {
+    fn rule(&self, ending: Ending) -> Option<&EndingRule> {
+        match ending {
+            Ending::Death => self.death.as_ref(),
+            Ending::Quit => self.quit.as_ref(),
+            Ending::NewRun => self.new_run.as_ref(),
+        }
+    }
+
+    pub fn policy(&self, ending: Ending) -> Policy {
+        self.rule(ending).map(|r| r.policy).unwrap_or_default()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,16 +90,99 @@ pub struct SplitsFile {
     #[serde(default)]
     pub start_trigger: Option<String>,
     #[serde(default)]
+    pub start_unless: Vec<String>,
+    #[serde(default)]
+    pub start_guards: Vec<Guard>,
+    #[serde(default)]
     pub reset_trigger: Option<String>,
+    #[serde(default)]
+    pub reset_unless: Vec<String>,
+    #[serde(default)]
+    pub reset_guards: Vec<Guard>,
+    #[serde(default)]
+    pub variables: Vec<Variable>,
+    #[serde(default)]
+    pub characters: Vec<String>, // Multi-character runs: `splits` repeats per character
+    #[serde(default)]
+    pub script_settings: serde_json::Map<String, serde_json::Value>,
+    #[serde(default)]
+    pub endings: Endings,
 }
 
 impl SplitsFile {
+    /// Load with the defaults of the settings declared by the `config.json`
+    /// next to the file, if there is one
     pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::load_with_settings(path, &GameSettings::defaults_from(&path.with_file_name("config.json")))
+    }
+
+    /// Load, keeping only the splits whose `when` the game settings allow.
+    /// A `when` that doesn't fit its setting, or a guard without exactly
+    /// one of `line` and `var`, is an error. The setting
+    /// values are also passed to scripts as `this.settings`.
+    pub fn load_with_settings(
+        path: &Path,
+        settings: &GameSettings,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
         let content = std::fs::read_to_string(path)?;
-        let splits: SplitsFile = serde_json::from_str(&content)?;
+        let mut splits: SplitsFile = serde_json::from_str(&content)?;
+        splits.check_guards()?;
+        splits.apply_settings(settings)?;
+        splits.expand_characters();
         Ok(splits)
     }
 
+    fn check_guards(&self) -> Result<(), String> {
+        let check = |guards: &[Guard], what: &str| {
+            guards
+                .iter()
+                .try_for_each(|guard| guard.check().map_err(|e| format!("{}: {}", what, e)))
+        };
+        check(&self.start_guards, "start trigger")?;
+        check(&self.reset_guards, "reset trigger")?;
+        for split in &self.splits {
+            check(&split.guards, &format!("split {:?}", split.name))?;
+        }
+        Ok(())
+    }
+
+    fn apply_settings(&mut self, settings: &GameSettings) -> Result<(), String> {
+        for split in &self.splits {
+            if let Some(ref when) = split.when {
+                settings
+                    .check(when)
+                    .map_err(|e| format!("split {:?}: {}", split.name, e))?;
+            }
+        }
+
+        self.splits
+            .retain(|split| split.when.as_ref().is_none_or(|when| settings.allows(when)));
+        self.script_settings.extend(settings.to_json());
+        Ok(())
+    }
+
+    /// For multi-character runs, repeat the splits for every character as
+    /// LiveSplit-style subsplits: "-Floor" for each floor and
+    /// "{Character} Floor" closing the character's group.
+    fn expand_characters(&mut self) {
+        if self.characters.is_empty() {
+            return;
+        }
+
+        let floors = std::mem::take(&mut self.splits);
+        for character in &self.characters {
+            for (i, floor) in floors.iter().enumerate() {
+                let mut split = floor.clone();
+                split.name = if i + 1 == floors.len() {
+                    format!("{{{}}} {}", character, floor.name)
+                } else {
+                    format!("-{}", floor.name)
+                };
+                self.splits.push(split);
+            }
+        }
+    }
+
     pub fn default_run() -> Self {
         SplitsFile {
             game: "Game".to_string(),
@@ -37,10 +192,149 @@ impl SplitsFile {
                     name: "Split 1".to_string(),
                     best_time_ms: None,
                     trigger: None,
+                    unless: Vec::new(),
+                    guards: Vec::new(),
+                    when: None,
+                    icon: None,
                 },
             ],
             start_trigger: None,
+            start_unless: Vec::new(),
+            start_guards: Vec::new(),
             reset_trigger: None,
+            reset_unless: Vec::new(),
+            reset_guards: Vec::new(),
+            variables: Vec::new(),
+            characters: Vec::new(),
+            script_settings: serde_json::Map::new(),
+            endings: Endings::default(),
         }
     }
+
+    pub fn start_watch_trigger(&self) -> Option<Trigger> {
+        self.start_trigger
+            .clone()
+            .map(|k| Trigger::new(k, self.start_unless.clone(), self.start_guards.clone()))
+    }
+
+    pub fn reset_watch_trigger(&self) -> Option<Trigger> {
+        self.reset_trigger
+            .clone()
+            .map(|k| Trigger::new(k, self.reset_unless.clone(), self.reset_guards.clone()))
+    }
+
+    pub fn ending_watch_triggers(&self) -> Vec<(Ending, Trigger)> {
+        [Ending::Death, Ending::Quit, Ending::NewRun]
+            .into_iter()
+            .filter_map(|ending| {
+                let rule = self.endings.rule(ending)?;
+                let keyword = rule.trigger.clone()?;
+                Some((ending, Trigger::new(keyword, rule.unless.clone(), Vec::new())))
+            })
+            .collect()
+    }
+
+    pub fn split_watch_triggers(&self) -> Vec<Option<Trigger>> {
+        self.splits
+            .iter()
+            .map(|s| {
+                s.trigger
+                    .clone()
+                    .map(|k| Trigger::new(k, s.unless.clone(), s.guards.clone()))
+            })
+            .collect()
+    }
+}
+
+/// The splits shown in a list of `visible` rows (0 for all of them), like
+/// LiveSplit: the current split with `preview` upcoming splits below it,
+/// moved by `scroll` rows, and the final split always in the last row. A
+/// single row shows just the current split.
+pub fn visible_splits(total: usize, current: usize, visible: usize, preview: usize, scroll: isize) -> Vec<usize> {
+    if visible == 0 || total <= visible {
+        return (0..total).collect();
+    }
+
+    let last = total - 1;
+    if visible == 1 {
+        return vec![(current as isize + scroll).clamp(0, last as isize) as usize];
+    }
+    let body = visible - 1; // Rows above the final split
+    let max_start = last - body;
+    let follow = (current + preview + 1).saturating_sub(body).min(max_start);
+    let start = (follow as isize + scroll).clamp(0, max_start as isize) as usize;
+
+    (start..start + body).chain(std::iter::once(last)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_settings_default_to_the_declared_ones() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("autosplitters/binding-of-isaac-rebirth");
+        let splits = SplitsFile::load(&dir.join("splits.json")).unwrap();
+        // "Split on every floor" is on by default
+        assert!(splits.splits.iter().any(|split| split.name == "B1"));
+    }
+
+    #[test]
+    fn test_guards_need_a_line_or_a_var() {
+        let path = std::env::temp_dir().join(format!("macsplit-guards-{}.json", std::process::id()));
+        let load = |guard: &str| {
+            std::fs::write(
+                &path,
+                format!(
+                    r#"{{ "game": "Game", "category": "Any%", "splits": [
+                        {{ "name": "Boss", "trigger": "Boss", "guards": [{}] }}
+                    ] }}"#,
+                    guard
+                ),
+            )
+            .unwrap();
+            SplitsFile::load(&path)
+        };
+
+        assert!(load(r#"{ "line": "Floor", "contains": "Womb" }"#).is_ok());
+        assert!(load(r#"{ "var": "character", "contains": "Isaac" }"#).is_ok());
+        let neither = load(r#"{ "contains": "Womb" }"#).unwrap_err().to_string();
+        assert!(neither.contains("\"Boss\""), "{neither}");
+        assert!(load(r#"{ "line": "Floor", "var": "character", "contains": "Womb" }"#).is_err());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_display_names_of_subsplits() {
+        assert_eq!(display_name("-B1"), "B1");
+        assert_eq!(display_name("{Isaac} Blue Baby"), "Isaac: Blue Baby");
+        assert_eq!(display_name("Blue Baby"), "Blue Baby");
+    }
+
+    #[test]
+    fn test_visible_splits_follow_current_and_keep_last() {
+        // Short runs show everything
+        assert_eq!(visible_splits(3, 0, 5, 1, 0), vec![0, 1, 2]);
+        assert_eq!(visible_splits(40, 0, 0, 1, 0).len(), 40);
+
+        assert_eq!(visible_splits(40, 0, 5, 1, 0), vec![0, 1, 2, 3, 39]);
+        // The current split (10) and one upcoming split stay in view
+        assert_eq!(visible_splits(40, 10, 5, 1, 0), vec![8, 9, 10, 11, 39]);
+        // Near the end the window stops at the final split
+        assert_eq!(visible_splits(40, 38, 5, 1, 0), vec![35, 36, 37, 38, 39]);
+        assert_eq!(visible_splits(40, 40, 5, 1, 0), vec![35, 36, 37, 38, 39]);
+
+        // A single row keeps the current split rather than the final one
+        assert_eq!(visible_splits(40, 10, 1, 1, 0), vec![10]);
+        assert_eq!(visible_splits(40, 40, 1, 1, 0), vec![39]);
+        assert_eq!(visible_splits(40, 10, 1, 1, -2), vec![8]);
+    }
+
+    #[test]
+    fn test_visible_splits_scroll() {
+        assert_eq!(visible_splits(40, 10, 5, 1, 3), vec![11, 12, 13, 14, 39]);
+        assert_eq!(visible_splits(40, 10, 5, 1, -20), vec![0, 1, 2, 3, 39]);
+        assert_eq!(visible_splits(40, 10, 5, 1, 100), vec![35, 36, 37, 38, 39]);
+    }
 }
@@ -0,0 +1,102 @@
+/// How a segment went over the attempts, in seconds
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SegmentStats {
+    pub gold: Option<f64>,
+    pub average: Option<f64>,
+    pub median: Option<f64>,
+    pub std_dev: Option<f64>,
+    pub reset_rate: Option<f64>, // Share of the attempts reaching the segment that ended in it
+    pub time_lost: Option<f64>,  // Average time lost against the gold
+}
+
+/// Statistics for every segment from its times in the run's segment history,
+/// practice included. `attempts` holds how many segments each attempt
+/// completed; one that stopped short of the last ended in the next segment.
+pub fn segment_stats(segment_times: &[Vec<f64>], golds: &[Option<f64>], attempts: &[usize]) -> Vec<SegmentStats> {
+    golds
+        .iter()
+        .enumerate()
+        .map(|(i, &gold)| {
+            let mut times = segment_times.get(i).cloned().unwrap_or_default();
+            times.sort_by(f64::total_cmp);
+
+            let reached = attempts.iter().filter(|&&completed| completed >= i).count();
+            let ended = attempts.iter().filter(|&&completed| completed == i).count();
+            let average = mean(&times);
+
+            SegmentStats {
+                gold,
+                average,
+                median: median(&times),
+                std_dev: average.map(|average| {
+                    (times.iter().map(|t| (t - average).powi(2)).sum::<f64>() / times.len() as f64).sqrt()
+                }),
+                reset_rate: (reached > 0).then(|| ended as f64 / reached as f64),
+                time_lost: average.zip(gold).map(|(average, gold)| average - gold),
+            }
+        })
+        .collect()
+}
+
+/// `1:05.3`, or `5.3` under a minute
+pub fn format_seconds(seconds: f64) -> String {
+    let sign = if seconds < 0.0 { "-" } else { "" };
+    let tenths = (seconds.abs() * 10.0).round() as u64;
+    let (minutes, tenths) = (tenths / 600, tenths % 600);
+    if minutes > 0 {
+        format!("{}{}:{:02}.{}", sign, minutes, tenths / 10, tenths % 10)
+    } else {
+        format!("{}{}.{}", sign, tenths / 10, tenths % 10)
+    }
+}
+
+fn mean(times: &[f64]) -> Option<f64> {
+    (!times.is_empty()).then(|| times.iter().sum::<f64>() / times.len() as f64)
+}
+
+/// Median of sorted times
+fn median(times: &[f64]) -> Option<f64> {
+    let middle = times.len() / 2;
+    match times.len() {
+        0 => None,
+        n if n % 2 == 0 => Some((times[middle - 1] + times[middle]) / 2.0),
+        _ => Some(times[middle]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_segment_stats() {
+        let segment_times = vec![vec![30.0, 34.0, 32.0], vec![40.0]];
+        // Two finished, one reset in the second segment, one in the first
+        let attempts = [2, 2, 1, 0];
+        let stats = segment_stats(&segment_times, &[Some(29.0), Some(38.0)], &attempts);
+
+        assert_eq!(stats[0].average, Some(32.0));
+        assert_eq!(stats[0].median, Some(32.0));
+        assert!((stats[0].std_dev.unwrap() - (8.0f64 / 3.0).sqrt()).abs() < 1e-9);
+        assert_eq!(stats[0].reset_rate, Some(0.25));
+        assert_eq!(stats[0].time_lost, Some(3.0));
+
+        assert_eq!(stats[1].average, Some(40.0));
+        assert_eq!(stats[1].std_dev, Some(0.0));
+        assert_eq!(stats[1].reset_rate, Some(1.0 / 3.0));
+        assert_eq!(stats[1].time_lost, Some(2.0));
+    }
+
+    #[test]
+    fn test_no_attempts() {
+        let stats = segment_stats(&[], &[None], &[]);
+        assert_eq!(stats, vec![SegmentStats::default()]);
+        assert_eq!(median(&[1.0, 2.0, 4.0, 10.0]), Some(3.0));
+    }
+
+    #[test]
+    fn test_format_seconds() {
+        assert_eq!(format_seconds(5.26), "5.3");
+        assert_eq!(format_seconds(-65.3), "-1:05.3");
+    }
+}
@@ -0,0 +1,336 @@
+use crate::session::Session;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Seconds before a split press in which the event that caused it is looked
+/// for; the runner presses split after seeing it.
+const DEFAULT_BEFORE: f64 = 3.0;
+/// Seconds after a split press still counted, for logs written late
+const DEFAULT_AFTER: f64 = 1.0;
+/// Candidates shown per split
+const SHOWN_CANDIDATES: usize = 3;
+
+/// A log line with the time it was written, in seconds
+#[derive(Debug, Clone)]
+pub struct TimedLine {
+    pub time: f64,
+    pub line: String,
+}
+
+/// A keyword proposed as the trigger for one split
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+    pub keyword: String,
+    pub offset: f64,       // When it was seen, relative to the split press
+    pub early_hits: usize, // Times it appeared since the previous split, which would split too early
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Window {
+    pub before: f64,
+    pub after: f64,
+}
+
+impl Default for Window {
+    fn default() -> Self {
+        Self { before: DEFAULT_BEFORE, after: DEFAULT_AFTER }
+    }
+}
+
+/// Read a timed log: one `<seconds><TAB><line>` entry per line
+pub fn load_timed_log(path: &Path) -> Result<Vec<TimedLine>, Box<dyn std::error::Error>> {
+    let content = std::fs::read_to_string(path)?;
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty())
+        .map(|(i, l)| {
+            let (time, line) = l.split_once('\t').ok_or_else(|| format!("Line {}: expected <seconds><TAB><line>", i + 1))?;
+            let time = parse_time(time).ok_or_else(|| format!("Line {}: bad time {:?}", i + 1, time))?;
+            Ok(TimedLine { time, line: line.to_string() })
+        })
+        .collect()
+}
+
+/// Seconds as `83.5` or `1:23.5` (or `h:mm:ss.s`)
+pub fn parse_time(text: &str) -> Option<f64> {
+    text.trim()
+        .split(':')
+        .try_fold(0.0, |total, part| Some(total * 60.0 + part.parse::<f64>().ok()?))
+}
+
+/// The part of a line worth matching on: the text after a timestamp or
+/// number prefix, up to the next digit, so floor numbers, seeds and
+/// timestamps don't make every line unique.
+fn keyword_of(line: &str) -> Option<String> {
+    // Digits and punctuation before the first word, like "[12:01:33] "
+    let words = line.find(char::is_alphabetic).unwrap_or(line.len());
+    let text = if line[..words].contains(|c: char| c.is_ascii_digit()) {
+        &line[words..]
+    } else {
+        line
+    };
+    let stable = match text.find(|c: char| c.is_ascii_digit()) {
+        Some(i) => &text[..i],
+        None => text,
+    };
+    let stable = stable.trim();
+    (stable.len() >= 4).then(|| stable.to_string())
+}
+
+/// For the split pressed at each of `split_times`, the keywords of lines
+/// seen around the press, best first: those that didn't appear since the
+/// previous split come first, then those closest to the press.
+pub fn suggest(log: &[TimedLine], split_times: &[f64], window: Window) -> Vec<Vec<Candidate>> {
+    let keywords: Vec<Option<String>> = log.iter().map(|l| keyword_of(&l.line)).collect();
+
+    split_times
+        .iter()
+        .enumerate()
+        .map(|(i, &split)| {
+            let previous = if i == 0 { f64::NEG_INFINITY } else { split_times[i - 1] };
+            let window_start = split - window.before;
+
+            // Closest sighting of each keyword around the press
+            let mut near: HashMap<&str, f64> = HashMap::new();
+            for (entry, keyword) in log.iter().zip(&keywords) {
+                let Some(keyword) = keyword else { continue };
+                let offset = entry.time - split;
+                if entry.time >= window_start && offset <= window.after {
+                    let best = near.entry(keyword.as_str()).or_insert(offset);
+                    if offset.abs() < best.abs() {
+                        *best = offset;
+                    }
+                }
+            }
+
+            let mut candidates: Vec<Candidate> = near
+                .into_iter()
+                .map(|(keyword, offset)| {
+                    let early_hits = log
+                        .iter()
+                        .zip(&keywords)
+                        .filter(|(entry, k)| {
+                            entry.time > previous && entry.time < window_start && k.as_deref() == Some(keyword)
+                        })
+                        .count();
+                    Candidate { keyword: keyword.to_string(), offset, early_hits }
+                })
+                .collect();
+
+            candidates.sort_by(|a, b| {
+                a.early_hits
+                    .cmp(&b.early_hits)
+                    .then(a.offset.abs().total_cmp(&b.offset.abs()))
+                    .then(b.keyword.len().cmp(&a.keyword.len()))
+            });
+            candidates
+        })
+        .collect()
+}
+
+/// A `splits.json` using the best candidate of each split
+pub fn draft_splits(
+    game: &str,
+    category: &str,
+    names: &[String],
+    start: Option<&Candidate>,
+    splits: &[Vec<Candidate>],
+) -> serde_json::Value {
+    let splits: Vec<serde_json::Value> = splits
+        .iter()
+        .enumerate()
+        .map(|(i, candidates)| {
+            let name = names.get(i).cloned().unwrap_or_else(|| format!("Split {}", i + 1));
+            match candidates.first() {
+                Some(best) => serde_json::json!({ "name": name, "trigger": best.keyword }),
+                None => serde_json::json!({ "name": name }),
+            }
+        })
+        .collect();
+
+    let mut draft = serde_json::json!({ "game": game, "category": category });
+    if let Some(start) = start {
+        draft["start_trigger"] = serde_json::json!(start.keyword);
+    }
+    draft["splits"] = serde_json::json!(splits);
+    draft
+}
+
+pub struct SuggestOptions {
+    pub split_times: Vec<f64>, // Empty to use the presses recorded in a session file
+    pub start_time: Option<f64>,
+    pub names: Vec<String>,
+    pub game: String,
+    pub category: String,
+    pub window: Window,
+}
+
+/// Session files from `--record` are JSON lines; anything else is a timed log
+fn is_session(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "jsonl")
+}
+
+/// The `suggest-triggers` command: print the candidates for every split and
+/// return the draft splits file. A recorded session supplies its own split
+/// presses, from its longest attempt, unless split times are given.
+pub fn run(log_path: &Path, options: &SuggestOptions) -> Result<String, Box<dyn std::error::Error>> {
+    let given: Vec<Option<f64>> = options.split_times.iter().copied().map(Some).collect();
+    let (log, split_times, start_time) = if is_session(log_path) {
+        let session = Session::load(log_path)?;
+        let log = session.timed_log();
+        match session.attempts().into_iter().max_by_key(|a| a.splits.iter().flatten().count()) {
+            Some(attempt) if given.is_empty() => (log, attempt.splits, options.start_time.or(attempt.start)),
+            _ => (log, given, options.start_time),
+        }
+    } else {
+        (load_timed_log(log_path)?, given, options.start_time)
+    };
+    if split_times.iter().all(Option::is_none) {
+        return Err("No split times given".into());
+    }
+
+    let start = start_time
+        .and_then(|t| suggest(&log, &[t], options.window).pop())
+        .unwrap_or_default();
+
+    // Skipped splits get no candidates
+    let pressed: Vec<f64> = split_times.iter().flatten().copied().collect();
+    let mut found = suggest(&log, &pressed, options.window).into_iter();
+    let splits: Vec<Vec<Candidate>> = split_times
+        .iter()
+        .map(|t| match t {
+            Some(_) => found.next().unwrap_or_default(),
+            None => Vec::new(),
+        })
+        .collect();
+
+    let report = |label: String, candidates: &[Candidate]| {
+        eprintln!("{}", label);
+        if candidates.is_empty() {
+            eprintln!("  (no log lines near this press)");
+        }
+        for c in candidates.iter().take(SHOWN_CANDIDATES) {
+            eprintln!("  {:+6.2}s  {:?}  ({} early)", c.offset, c.keyword, c.early_hits);
+        }
+    };
+
+    if let Some(time) = start_time {
+        report(format!("Start at {:.2}s", time), &start);
+    }
+    for (i, candidates) in splits.iter().enumerate() {
+        let name = options.names.get(i).cloned().unwrap_or_else(|| format!("Split {}", i + 1));
+        match split_times[i] {
+            Some(time) => report(format!("{} at {:.2}s", name, time), candidates),
+            None => eprintln!("{} skipped", name),
+        }
+    }
+
+    let draft = draft_splits(&options.game, &options.category, &options.names, start.first(), &splits);
+    Ok(serde_json::to_string_pretty(&draft)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(lines: &[(f64, &str)]) -> Vec<TimedLine> {
+        lines
+            .iter()
+            .map(|(time, line)| TimedLine { time: *time, line: line.to_string() })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_time() {
+        assert_eq!(parse_time("83.5"), Some(83.5));
+        assert_eq!(parse_time("1:23.5"), Some(83.5));
+        assert_eq!(parse_time("1:00:00"), Some(3600.0));
+        assert_eq!(parse_time("soon"), None);
+    }
+
+    #[test]
+    fn test_prefers_lines_not_seen_since_previous_split() {
+        let log = log(&[
+            (1.0, "[INFO] - Room 12 loaded"),
+            (9.0, "[INFO] - Room 13 loaded"),
+            (9.5, "[INFO] - Level::Init m_Stage 2, m_AltStage 0"),
+            (20.0, "[INFO] - Room 14 loaded"),
+            (30.0, "[INFO] - Room 15 loaded"),
+            (30.2, "[INFO] - Level::Init m_Stage 3, m_AltStage 0"),
+        ]);
+
+        let suggestions = suggest(&log, &[10.0, 31.0], Window::default());
+
+        // Rooms load all the time; the stage change only at the split
+        for candidates in &suggestions {
+            assert_eq!(candidates[0].keyword, "[INFO] - Level::Init m_Stage");
+            assert_eq!(candidates[0].early_hits, 0);
+        }
+        assert_eq!(suggestions[1][1].keyword, "[INFO] - Room");
+        assert_eq!(suggestions[1][1].early_hits, 1);
+    }
+
+    #[test]
+    fn test_keywords_skip_timestamp_prefixes() {
+        assert_eq!(keyword_of("[12:01:33] Boss defeated").as_deref(), Some("Boss defeated"));
+        assert_eq!(keyword_of("0.512 Level::Init m_Stage 2").as_deref(), Some("Level::Init m_Stage"));
+        assert_eq!(keyword_of("[INFO] - Room 12 loaded").as_deref(), Some("[INFO] - Room"));
+        assert_eq!(keyword_of("[12:01:33] 42"), None);
+
+        let log = log(&[
+            (1.0, "[12:00:01] Room loaded"),
+            (9.5, "[12:00:09] Boss defeated"),
+            (20.0, "[12:00:20] Room loaded"),
+            (30.2, "[12:00:30] Boss defeated"),
+        ]);
+        let suggestions = suggest(&log, &[10.0, 31.0], Window::default());
+        for candidates in &suggestions {
+            assert_eq!(candidates[0].keyword, "Boss defeated");
+        }
+    }
+
+    #[test]
+    fn test_draft_uses_best_candidates() {
+        let best = Candidate { keyword: "Boss defeated".to_string(), offset: -0.5, early_hits: 0 };
+        let draft = draft_splits("Game", "Any%", &["Boss".to_string()], None, &[vec![best], vec![]]);
+
+        assert_eq!(draft["splits"][0]["trigger"], "Boss defeated");
+        assert_eq!(draft["splits"][0]["name"], "Boss");
+        assert_eq!(draft["splits"][1]["name"], "Split 2");
+        assert!(draft["splits"][1].get("trigger").is_none());
+        assert!(draft.get("start_trigger").is_none());
+    }
+
+    #[test]
+    fn test_run_uses_presses_recorded_in_session() {
+        let path = std::env::temp_dir().join(format!("macsplit-session-{}.jsonl", std::process::id()));
+        std::fs::write(
+            &path,
+            [
+                r#"{"t":0.5,"kind":"log","line":"Game started"}"#,
+                r#"{"t":1.0,"kind":"action","action":"start"}"#,
+                r#"{"t":9.0,"kind":"action","action":"skip"}"#,
+                r#"{"t":19.5,"kind":"log","line":"Boss defeated"}"#,
+                r#"{"t":20.0,"kind":"action","action":"split"}"#,
+            ]
+            .join("\n"),
+        )
+        .unwrap();
+
+        let options = SuggestOptions {
+            split_times: Vec::new(),
+            start_time: None,
+            names: Vec::new(),
+            game: "Game".to_string(),
+            category: "Any%".to_string(),
+            window: Window::default(),
+        };
+        let draft: serde_json::Value = serde_json::from_str(&run(&path, &options).unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(draft["start_trigger"], "Game started");
+        assert!(draft["splits"][0].get("trigger").is_none());
+        assert_eq!(draft["splits"][1]["trigger"], "Boss defeated");
+    }
+}
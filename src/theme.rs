@@ -0,0 +1,293 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// A color written as `"#rrggbb"` in theme files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Rgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Rgb {
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b }
+    }
+}
+
+impl TryFrom<String> for Rgb {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        let hex = text.strip_prefix('#').unwrap_or(&text);
+        let channel = |i: usize| hex.get(i..i + 2).and_then(|c| u8::from_str_radix(c, 16).ok());
+        match (hex.len(), channel(0), channel(2), channel(4)) {
+            (6, Some(r), Some(g), Some(b)) => Ok(Self { r, g, b }),
+            _ => Err(format!("bad color {:?}, expected \"#rrggbb\"", text)),
+        }
+    }
+}
+
+impl From<Rgb> for String {
+    fn from(color: Rgb) -> Self {
+        format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b)
+    }
+}
+
+/// How a split went against the comparison, as LiveSplit colors it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeltaKind {
+    AheadGaining,
+    AheadLosing,
+    BehindGaining,
+    BehindLosing,
+    BestSegment,
+}
+
+impl DeltaKind {
+    /// `delta` is the run's delta at a split and `previous_delta` the one at
+    /// the split before, if any. A new best segment wins over both.
+    pub fn of(delta: f64, previous_delta: Option<f64>, best_segment: bool) -> Self {
+        if best_segment {
+            DeltaKind::BestSegment
+        } else if delta < 0.0 {
+            match previous_delta {
+                Some(previous) if delta > previous => DeltaKind::AheadLosing,
+                _ => DeltaKind::AheadGaining,
+            }
+        } else {
+            match previous_delta {
+                Some(previous) if delta < previous => DeltaKind::BehindGaining,
+                _ => DeltaKind::BehindLosing,
+            }
+        }
+    }
+}
+
+/// Colors and fonts of both front ends. Theme files may leave out any field
+/// to keep the dark theme's value.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    #[serde(default)] // Not the dark theme's name, so the file name can stand in
+    pub name: String,
+    pub background: Rgb,
+    pub header_background: Rgb,
+    pub split_background: Rgb,
+    pub current_split_background: Rgb,
+    pub text: Rgb,
+    pub text_dim: Rgb,
+    pub accent: Rgb,
+    pub ahead_gaining: Rgb,
+    pub ahead_losing: Rgb,
+    pub behind_gaining: Rgb,
+    pub behind_losing: Rgb,
+    pub best_segment: Rgb,
+    pub paused: Rgb,
+    pub not_running: Rgb,
+    pub personal_best: Rgb,
+    pub error: Rgb,
+    pub font: Option<PathBuf>, // .ttf/.otf used for all GUI text
+    pub text_size: f32,
+    pub timer_size: f32,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+impl Theme {
+    pub fn dark() -> Self {
+        Self {
+            name: "Dark".to_string(),
+            background: Rgb::new(20, 20, 25),
+            header_background: Rgb::new(30, 30, 40),
+            split_background: Rgb::new(25, 25, 35),
+            current_split_background: Rgb::new(40, 40, 60),
+            text: Rgb::new(255, 255, 255),
+            text_dim: Rgb::new(170, 170, 170),
+            accent: Rgb::new(139, 69, 255),
+            ahead_gaining: Rgb::new(50, 205, 50),
+            ahead_losing: Rgb::new(82, 204, 115),
+            behind_gaining: Rgb::new(204, 92, 82),
+            behind_losing: Rgb::new(220, 60, 60),
+            best_segment: Rgb::new(255, 215, 0),
+            paused: Rgb::new(255, 215, 0),
+            not_running: Rgb::new(255, 255, 255),
+            personal_best: Rgb::new(100, 149, 237),
+            error: Rgb::new(220, 60, 60),
+            font: None,
+            text_size: 14.0,
+            timer_size: 48.0,
+        }
+    }
+
+    pub fn light() -> Self {
+        Self {
+            name: "Light".to_string(),
+            background: Rgb::new(244, 244, 246),
+            header_background: Rgb::new(228, 228, 234),
+            split_background: Rgb::new(236, 236, 240),
+            current_split_background: Rgb::new(208, 212, 240),
+            text: Rgb::new(22, 22, 28),
+            text_dim: Rgb::new(90, 90, 102),
+            accent: Rgb::new(106, 47, 214),
+            ahead_gaining: Rgb::new(0, 138, 37),
+            ahead_losing: Rgb::new(63, 154, 90),
+            behind_gaining: Rgb::new(181, 72, 63),
+            behind_losing: Rgb::new(184, 16, 0),
+            best_segment: Rgb::new(166, 124, 0),
+            paused: Rgb::new(122, 122, 122),
+            not_running: Rgb::new(22, 22, 28),
+            personal_best: Rgb::new(11, 111, 196),
+            error: Rgb::new(184, 16, 0),
+            ..Self::dark()
+        }
+    }
+
+    pub fn high_contrast() -> Self {
+        Self {
+            name: "High contrast".to_string(),
+            background: Rgb::new(0, 0, 0),
+            header_background: Rgb::new(0, 0, 0),
+            split_background: Rgb::new(0, 0, 0),
+            current_split_background: Rgb::new(48, 48, 48),
+            text: Rgb::new(255, 255, 255),
+            text_dim: Rgb::new(224, 224, 224),
+            accent: Rgb::new(255, 255, 0),
+            ahead_gaining: Rgb::new(0, 255, 0),
+            ahead_losing: Rgb::new(128, 255, 128),
+            behind_gaining: Rgb::new(255, 128, 128),
+            behind_losing: Rgb::new(255, 0, 0),
+            best_segment: Rgb::new(255, 215, 0),
+            paused: Rgb::new(0, 255, 255),
+            not_running: Rgb::new(255, 255, 255),
+            personal_best: Rgb::new(0, 191, 255),
+            error: Rgb::new(255, 0, 0),
+            text_size: 16.0,
+            timer_size: 56.0,
+            ..Self::dark()
+        }
+    }
+
+    /// The Okabe-Ito palette: blue for ahead and orange for behind, which
+    /// stay apart for red-green color blindness
+    pub fn colorblind_safe() -> Self {
+        Self {
+            name: "Colorblind safe".to_string(),
+            accent: Rgb::new(204, 121, 167),
+            ahead_gaining: Rgb::new(86, 180, 233),
+            ahead_losing: Rgb::new(156, 203, 232),
+            behind_gaining: Rgb::new(230, 159, 0),
+            behind_losing: Rgb::new(213, 94, 0),
+            best_segment: Rgb::new(240, 228, 66),
+            paused: Rgb::new(204, 121, 167),
+            personal_best: Rgb::new(0, 158, 115),
+            error: Rgb::new(213, 94, 0),
+            ..Self::dark()
+        }
+    }
+
+    pub fn delta_color(&self, kind: DeltaKind) -> Rgb {
+        match kind {
+            DeltaKind::AheadGaining => self.ahead_gaining,
+            DeltaKind::AheadLosing => self.ahead_losing,
+            DeltaKind::BehindGaining => self.behind_gaining,
+            DeltaKind::BehindLosing => self.behind_losing,
+            DeltaKind::BestSegment => self.best_segment,
+        }
+    }
+
+    pub fn builtin() -> Vec<Self> {
+        vec![Self::dark(), Self::light(), Self::high_contrast(), Self::colorblind_safe()]
+    }
+
+    /// Read a theme file. Without a `name`, the file name is used.
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let content = std::fs::read_to_string(path)?;
+        let mut theme: Theme =
+            serde_json::from_str(&content).map_err(|e| format!("Bad theme {}: {}", path.display(), e))?;
+        if theme.name.is_empty() {
+            theme.name = path
+                .file_stem()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+        }
+        // Fonts are relative to the theme file
+        if let (Some(font), Some(dir)) = (theme.font.as_mut(), path.parent()) {
+            *font = dir.join(&*font);
+        }
+        Ok(theme)
+    }
+
+    /// The built-in themes followed by the user's, from the `themes` config
+    /// folder. Theme files that don't load are skipped.
+    pub fn all() -> Vec<Self> {
+        let mut themes = Self::builtin();
+
+        let dir = dirs_next::config_dir().map(|dir| dir.join("MacSplit").join("themes"));
+        let Some(Ok(entries)) = dir.map(std::fs::read_dir) else {
+            return themes;
+        };
+        let mut files: Vec<PathBuf> = entries
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        files.sort();
+
+        for theme in files.iter().filter_map(|path| Self::load(path).ok()) {
+            match themes.iter_mut().find(|t| t.name == theme.name) {
+                Some(existing) => *existing = theme,
+                None => themes.push(theme),
+            }
+        }
+        themes
+    }
+
+    /// Index of the theme called `name`, or of the dark theme
+    pub fn find(themes: &[Self], name: Option<&str>) -> usize {
+        name.and_then(|name| themes.iter().position(|t| t.name.eq_ignore_ascii_case(name)))
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_colors_parse_as_hex() {
+        assert_eq!(Rgb::try_from("#32cd32".to_string()), Ok(Rgb::new(50, 205, 50)));
+        assert_eq!(Rgb::try_from("FFD700".to_string()), Ok(Rgb::new(255, 215, 0)));
+        assert!(Rgb::try_from("#fff".to_string()).is_err());
+        assert_eq!(String::from(Rgb::new(20, 20, 25)), "#141419");
+    }
+
+    #[test]
+    fn test_delta_kinds() {
+        assert_eq!(DeltaKind::of(-2.0, None, false), DeltaKind::AheadGaining);
+        assert_eq!(DeltaKind::of(-2.0, Some(-4.0), false), DeltaKind::AheadLosing);
+        assert_eq!(DeltaKind::of(2.0, Some(4.0), false), DeltaKind::BehindGaining);
+        assert_eq!(DeltaKind::of(0.0, Some(-1.0), false), DeltaKind::BehindLosing);
+        assert_eq!(DeltaKind::of(2.0, Some(1.0), true), DeltaKind::BestSegment);
+    }
+
+    #[test]
+    fn test_partial_theme_keeps_dark_defaults() {
+        let theme: Theme =
+            serde_json::from_str(r##"{ "name": "Mine", "background": "#000000", "timer_size": 60 }"##).unwrap();
+
+        assert_eq!(theme.name, "Mine");
+        assert_eq!(theme.background, Rgb::new(0, 0, 0));
+        assert_eq!(theme.timer_size, 60.0);
+        assert_eq!(theme.best_segment, Theme::dark().best_segment);
+
+        let themes = Theme::builtin();
+        assert_eq!(themes[Theme::find(&themes, Some("light"))].name, "Light");
+        assert_eq!(Theme::find(&themes, Some("missing")), 0);
+    }
+}
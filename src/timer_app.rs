@@ -1,24 +1,74 @@
-use crate::splits::SplitsFile;
+use crate::gui::graph_line;
+use crate::history::{AttemptHistory, Outcome};
+use crate::layout;
+use crate::memory::{MemoryConfig, MemoryWatcher};
+use crate::screen::{self, Frame};
+use crate::session::{ManualAction, SessionRecorder};
+use crate::settings::Preferences;
+use crate::splits::{visible_splits, SplitDefinition, SplitsFile};
+use crate::stats;
+use crate::theme::{DeltaKind, Rgb, Theme};
+use crate::wasm::WasmAutosplitter;
 use crate::watcher::{LogWatcher, WatchEvent};
 use crossterm::{
     cursor,
     event::{self, Event, KeyCode, KeyEventKind},
-    execute,
+    execute, queue,
     style::{Color, Print, SetForegroundColor, ResetColor},
     terminal::{self, ClearType},
 };
-use livesplit_core::{Run, Segment, Timer, TimerPhase, TimeSpan};
+use livesplit_core::{Run, Segment, SharedTimer, Timer, TimerPhase, TimeSpan, TimingMethod};
 use std::io::{stdout, Write};
-use std::path::PathBuf;
-use std::time::Duration;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Log lines shown by `--debug-watch`
+const DEBUG_ROWS: usize = 12;
+
+/// Rows the timer, controls and messages take besides the splits list
+const RESERVED_ROWS: usize = 10;
+
+/// Columns of the splits list besides the name
+const BULLET_WIDTH: usize = 6;
+const DELTA_WIDTH: usize = 9;
+const TIME_WIDTH: usize = 11;
+
+/// Narrower terminals leave out the delta column
+const MIN_DELTA_WIDTH: usize = 40;
+
+/// How often to try opening a log that couldn't be watched
+const LOG_RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How the splits list is drawn
+struct View<'a> {
+    theme: &'a Theme,
+    visible_splits: usize, // 0 for all
+    preview: usize,
+    scroll: isize, // Rows scrolled away from the current split
+    auto_split: bool, // An autosplitter is driving the timer
+    wasm_error: Option<String>, // Why the WebAssembly autosplitter stopped
+}
+
+/// The `--watch` log, opened by the main loop and re-opened until it can be
+/// read
+struct LogSource {
+    path: PathBuf,
+    script_dir: Option<PathBuf>, // The splits file's folder, for its script
+    debug: bool,                 // Keep the lines for the debug console
+    last_attempt: Option<Instant>,
+}
 
 pub fn run(
     splits_path: Option<PathBuf>,
     watch_path: Option<PathBuf>,
+    autosplitter_path: Option<PathBuf>,
+    debug_watch: bool,
+    record_path: Option<PathBuf>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     // Load splits
     let splits_file = match splits_path {
-        Some(ref path) => SplitsFile::load(path)?,
+        Some(ref path) => SplitsFile::load(path)
+            .map_err(|e| format!("Bad splits file {}: {}", path.display(), e))?,
         None => SplitsFile::default_run(),
     };
 
@@ -30,32 +80,63 @@ pub fn run(
     for split in &splits_file.splits {
         let mut segment = Segment::new(&split.name);
         if let Some(best_ms) = split.best_time_ms {
+            let best = Some(TimeSpan::from_milliseconds(best_ms as f64));
             let time = livesplit_core::Time::new()
-                .with_real_time(Some(TimeSpan::from_milliseconds(best_ms as f64)));
+                .with_real_time(best)
+                .with_game_time(best);
             segment.set_best_segment_time(time);
         }
         run.push_segment(segment);
     }
+    // With the earlier attempts at the splits
+    let history = AttemptHistory::new(&splits_file.game, &splits_file.category);
+    history.load_run(&mut run);
 
     // Create timer
     let mut timer = Timer::new(run).map_err(|_| "Failed to create timer")?;
+    layout::pick_comparison(&mut timer);
+    let timer = timer.into_shared();
+
+    // Load WebAssembly autosplitter if provided; it controls game time
+    let mut wasm = match autosplitter_path {
+        Some(ref path) => {
+            let wasm = WasmAutosplitter::load(path, timer.clone())?;
+            timer
+                .write()
+                .unwrap()
+                .set_current_timing_method(TimingMethod::GameTime);
+            Some(wasm)
+        }
+        None => None,
+    };
 
-    // Setup log watcher if path provided
-    let mut watcher = if let Some(ref path) = watch_path {
-        let split_triggers: Vec<Option<String>> = splits_file
-            .splits
-            .iter()
-            .map(|s| s.trigger.clone())
-            .collect();
-
-        Some(LogWatcher::new(
-            path.clone(),
-            splits_file.start_trigger.clone(),
-            splits_file.reset_trigger.clone(),
-            split_triggers,
-        )?)
-    } else {
-        None
+    // Watch the log if a path was provided
+    let log = watch_path.map(|path| LogSource {
+        path,
+        script_dir: splits_path.as_ref().and_then(|p| p.parent()).map(Path::to_path_buf),
+        debug: debug_watch,
+        last_attempt: None,
+    });
+
+    // Read the game's memory if the config.json next to the splits file
+    // declares it
+    let memory = match splits_path.as_ref().and_then(|p| p.parent()) {
+        Some(dir) => match MemoryConfig::load_for_dir(dir)? {
+            Some(config) => Some(
+                MemoryWatcher::new(config).map_err(|e| format!("Memory autosplitter: {}", e))?,
+            ),
+            None => None,
+        },
+        None => None,
+    };
+
+    let recorder = match record_path {
+        Some(ref path) => {
+            let mut recorder = SessionRecorder::create(path)?;
+            recorder.splits(&splits_file.game, &splits_file.category);
+            Some(recorder)
+        }
+        None => None,
     };
 
     // Setup terminal
@@ -64,7 +145,7 @@ pub fn run(
     execute!(stdout, terminal::EnterAlternateScreen, cursor::Hide)?;
 
     // Main loop
-    let result = main_loop(&mut timer, &mut watcher, &splits_file);
+    let result = main_loop(&timer, log, memory, &splits_file, history, recorder, wasm.as_mut());
 
     // Cleanup terminal
     execute!(stdout, terminal::LeaveAlternateScreen, cursor::Show)?;
@@ -74,16 +155,69 @@ pub fn run(
 }
 
 fn main_loop(
-    timer: &mut Timer,
-    watcher: &mut Option<LogWatcher>,
+    shared_timer: &SharedTimer,
+    mut log: Option<LogSource>,
+    mut memory: Option<MemoryWatcher>,
     splits_file: &SplitsFile,
+    mut history: AttemptHistory,
+    mut recorder: Option<SessionRecorder>,
+    mut wasm: Option<&mut WasmAutosplitter>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let mut stdout = stdout();
+    let debug_watch = log.as_ref().is_some_and(|source| source.debug);
+    let mut watcher: Option<LogWatcher> = None;
+    let mut status: Option<String> = None; // Why the log isn't being watched
+    let mut preferences = Preferences::load();
+    let themes = Theme::all();
+    let mut theme_index = Theme::find(&themes, preferences.theme.as_deref());
+    let mut scroll: isize = 0;
+    let mut shown_split: Option<usize> = None; // Scrolling is undone when this changes
+    let mut previous: Option<Frame> = None; // What is on screen
 
     loop {
-        // Poll log watcher for auto-split events
+        // (Re)attach to the log until it can be opened
+        if let Some(ref mut source) = log {
+            if watcher.is_none()
+                && source.last_attempt.is_none_or(|t| t.elapsed() >= LOG_RETRY_INTERVAL)
+            {
+                source.last_attempt = Some(Instant::now());
+                let attached = LogWatcher::for_splits(source.path.clone(), splits_file, source.script_dir.as_deref())
+                    .map(|w| if debug_watch { w.with_debug() } else { w })
+                    .map(|w| if recorder.is_some() { w.with_recording() } else { w });
+                match attached {
+                    Ok(mut w) => {
+                        // Attached mid-run: continue from the timer's split
+                        let index = shared_timer.read().unwrap().current_split_index().unwrap_or(0);
+                        w.set_split_index(index);
+                        watcher = Some(w);
+                        status = None;
+                    }
+                    Err(e) => status = Some(e.to_string()),
+                }
+            }
+        }
+
+        // Poll the log, the game's memory and the WebAssembly autosplitter
+        // for auto-split events
+        let mut events = Vec::new();
         if let Some(ref mut w) = watcher {
-            for event in w.poll() {
+            events.extend(w.poll());
+            if let Some(ref mut recorder) = recorder {
+                for line in w.take_recorded() {
+                    recorder.log_line(&line);
+                }
+            }
+        }
+        if let Some(ref mut m) = memory {
+            events.extend(m.poll());
+        }
+        if let Some(ref mut wasm) = wasm {
+            let index = shared_timer.read().unwrap().current_split_index().unwrap_or(0);
+            events.extend(wasm.poll(index));
+        }
+        {
+            let mut timer = shared_timer.write().unwrap();
+            for event in events {
                 match event {
                     WatchEvent::Start => {
                         if timer.current_phase() == TimerPhase::NotRunning {
@@ -93,11 +227,42 @@ fn main_loop(
                     WatchEvent::Split(_) => {
                         if timer.current_phase() == TimerPhase::Running {
                             timer.split();
+                            if timer.current_phase() == TimerPhase::Ended {
+                                history.record(&timer, Outcome::Finished, None);
+                            }
                         }
                     }
                     WatchEvent::Reset => {
+                        history.record(&timer, Outcome::Reset, None);
                         timer.reset(true);
-                        w.reset_split_index();
+                        history.save_run(timer.run());
+                        layout::pick_comparison(&mut timer);
+                        sync_split_index(&timer, &mut watcher, &mut memory);
+                    }
+                    WatchEvent::Pause => {
+                        if timer.current_phase() == TimerPhase::Running {
+                            timer.pause();
+                        }
+                    }
+                    WatchEvent::Resume => {
+                        if timer.current_phase() == TimerPhase::Paused {
+                            timer.resume();
+                        }
+                    }
+                    WatchEvent::Skip(_) => {
+                        if timer.current_phase() == TimerPhase::Running {
+                            timer.skip_split();
+                        }
+                    }
+                    WatchEvent::Ended(ending) => {
+                        let policy = splits_file.endings.policy(ending);
+                        history.record(&timer, ending.into(), Some(policy));
+                        if policy.apply(&mut timer) {
+                            timer.reset(true);
+                            history.save_run(timer.run());
+                            layout::pick_comparison(&mut timer);
+                            sync_split_index(&timer, &mut watcher, &mut memory);
+                        }
                     }
                 }
             }
@@ -105,44 +270,84 @@ fn main_loop(
 
         // Handle keyboard input
         if event::poll(Duration::from_millis(16))? {
-            if let Event::Key(key) = event::read()? {
+            let event = event::read()?;
+            if let Event::Resize(..) = event {
+                previous = None;
+            }
+            if let Event::Key(key) = event {
                 if key.kind == KeyEventKind::Press {
+                    let mut timer = shared_timer.write().unwrap();
+                    let mut record = |action| {
+                        if let Some(ref mut recorder) = recorder {
+                            recorder.action(action);
+                        }
+                    };
                     match key.code {
                         KeyCode::Char('q') | KeyCode::Esc => break,
+                        KeyCode::Up => scroll = (scroll - 1).max(-(splits_file.splits.len() as isize)),
+                        KeyCode::Down => scroll = (scroll + 1).min(splits_file.splits.len() as isize),
+                        KeyCode::Char('t') => {
+                            theme_index = (theme_index + 1) % themes.len();
+                            preferences.theme = Some(themes[theme_index].name.clone());
+                            preferences.save();
+                        }
+                        KeyCode::Char('a') => {
+                            // Retry the log right away
+                            if let Some(ref mut source) = log {
+                                source.last_attempt = None;
+                            }
+                        }
                         KeyCode::Char(' ') => {
                             match timer.current_phase() {
-                                TimerPhase::NotRunning => timer.start(),
-                                TimerPhase::Running => timer.split(),
+                                TimerPhase::NotRunning => {
+                                    timer.start();
+                                    record(ManualAction::Start);
+                                    sync_split_index(&timer, &mut watcher, &mut memory);
+                                }
+                                TimerPhase::Running => {
+                                    timer.split();
+                                    record(ManualAction::Split);
+                                    if timer.current_phase() == TimerPhase::Ended {
+                                        history.record(&timer, Outcome::Finished, None);
+                                    }
+                                }
                                 TimerPhase::Ended => {}
-                                TimerPhase::Paused => timer.resume(),
+                                TimerPhase::Paused => {
+                                    timer.resume();
+                                    record(ManualAction::Resume);
+                                }
                             }
                         }
                         KeyCode::Char('r') => {
+                            history.record(&timer, Outcome::ManualReset, None);
                             timer.reset(true);
-                            if let Some(ref mut w) = watcher {
-                                w.reset_split_index();
-                            }
+                            history.save_run(timer.run());
+                            layout::pick_comparison(&mut timer);
+                            record(ManualAction::Reset);
+                            sync_split_index(&timer, &mut watcher, &mut memory);
                         }
                         KeyCode::Char('p') => {
                             match timer.current_phase() {
-                                TimerPhase::Running => timer.pause(),
-                                TimerPhase::Paused => timer.resume(),
+                                TimerPhase::Running => {
+                                    timer.pause();
+                                    record(ManualAction::Pause);
+                                }
+                                TimerPhase::Paused => {
+                                    timer.resume();
+                                    record(ManualAction::Resume);
+                                }
                                 _ => {}
                             }
                         }
                         KeyCode::Char('u') => {
                             timer.undo_split();
-                            if let Some(ref mut w) = watcher {
-                                let idx = timer.current_split_index().unwrap_or(0);
-                                w.set_split_index(idx);
-                            }
+                            record(ManualAction::Undo);
+                            sync_split_index(&timer, &mut watcher, &mut memory);
                         }
                         KeyCode::Char('s') => {
                             timer.skip_split();
-                            if let Some(ref mut w) = watcher {
-                                let idx = timer.current_split_index().unwrap_or(0);
-                                w.set_split_index(idx);
-                            }
+                            record(ManualAction::Skip);
+                            sync_split_index(&timer, &mut watcher, &mut memory);
                         }
                         _ => {}
                     }
@@ -151,7 +356,58 @@ fn main_loop(
         }
 
         // Render UI
-        render(&mut stdout, timer, splits_file, watcher.is_some())?;
+        let timer = shared_timer.read().unwrap();
+        if shown_split != timer.current_split_index() {
+            shown_split = timer.current_split_index();
+            scroll = 0;
+        }
+
+        // As many splits as fit, or fewer if the user asked for fewer
+        let (width, height) = terminal::size().map(|(w, h)| (w as usize, h as usize)).unwrap_or((80, 24));
+        let info_rows = watcher
+            .as_ref()
+            .map(|w| w.info_rows().len() + usize::from(w.script_error().is_some()))
+            .unwrap_or(0)
+            + usize::from(memory.as_ref().is_some_and(|m| !m.is_attached()));
+        let wasm_error = wasm.as_ref().and_then(|w| w.error());
+        let info_rows = info_rows + usize::from(wasm_error.is_some());
+        let debug_rows = if debug_watch { DEBUG_ROWS + 2 } else { 0 };
+        let fit = height.saturating_sub(RESERVED_ROWS + info_rows + debug_rows).max(1);
+        let theme = &themes[theme_index];
+        let view = View {
+            theme,
+            visible_splits: preferences.visible_splits.filter(|&n| n > 0).map_or(fit, |n| n.min(fit)),
+            preview: preferences.split_preview,
+            scroll,
+            auto_split: watcher.is_some()
+                || (wasm.is_some() && wasm_error.is_none())
+                || memory.as_ref().is_some_and(MemoryWatcher::is_attached),
+            wasm_error,
+        };
+
+        let mut frame = Frame::new(width, height);
+        render(
+            &mut frame,
+            &timer,
+            splits_file,
+            watcher.as_ref(),
+            memory.as_ref(),
+            status.as_deref(),
+            &view,
+        );
+        if let (true, Some(w)) = (debug_watch, watcher.as_ref()) {
+            render_debug(&mut frame, w, theme);
+        }
+        draw(&mut stdout, &frame, previous.as_ref())?;
+        previous = Some(frame);
+    }
+
+    // A run still going, or finished but not reset, goes into the history
+    let mut timer = shared_timer.write().unwrap();
+    if timer.current_phase() != TimerPhase::NotRunning {
+        history.record(&timer, Outcome::Closed, None);
+        timer.reset(true);
+        history.save_run(timer.run());
     }
 
     Ok(())
@@ -181,102 +437,248 @@ fn format_time(time_span: Option<TimeSpan>) -> String {
     }
 }
 
+fn color(c: Rgb) -> Color {
+    Color::Rgb { r: c.r, g: c.g, b: c.b }
+}
+
+/// Indent subsplits under their group
+fn subsplit_name(split: &SplitDefinition) -> String {
+    if split.is_subsplit() {
+        format!("  {}", split.display_name())
+    } else {
+        split.display_name().to_string()
+    }
+}
+
+/// A delta against the comparison, like `+5.3` or `-1:05.3`
+fn format_delta(seconds: f64) -> String {
+    let sign = if seconds < 0.0 { "" } else { "+" };
+    format!("{}{}", sign, stats::format_seconds(seconds))
+}
+
+/// Write the rows of `frame` that differ from what is on screen. A resized
+/// terminal is cleared and drawn again.
+fn draw(stdout: &mut std::io::Stdout, frame: &Frame, previous: Option<&Frame>) -> std::io::Result<()> {
+    let rows = match frame.changed_rows(previous) {
+        Some(rows) => rows,
+        None => {
+            queue!(stdout, terminal::Clear(ClearType::All))?;
+            (0..frame.lines.len().min(frame.height)).collect()
+        }
+    };
+
+    for row in rows {
+        queue!(stdout, cursor::MoveTo(0, row as u16))?;
+        if let Some(line) = frame.lines.get(row) {
+            for span in line.cut(frame.width).spans {
+                queue!(stdout, SetForegroundColor(color(span.color)), Print(span.text))?;
+            }
+        }
+        queue!(stdout, ResetColor, terminal::Clear(ClearType::UntilNewLine))?;
+    }
+    stdout.flush()
+}
+
+/// The `--debug-watch` panel: the latest log lines and what they matched
+fn render_debug(frame: &mut Frame, watcher: &LogWatcher, theme: &Theme) {
+    let width = frame.width.saturating_sub(2);
+
+    frame.blank();
+    frame.line().span(theme.text_dim, format!(" {}", "─".repeat(width.min(40))));
+    frame.line().span(
+        theme.text,
+        format!(" Split index: {}  Waiting for: {}", watcher.split_index(), watcher.waiting_for()),
+    );
+
+    let lines: Vec<_> = watcher.debug_lines().collect();
+    for line in &lines[lines.len().saturating_sub(DEBUG_ROWS)..] {
+        let (text, color) = if let Some(ref error) = line.error {
+            (format!("{}  -> {}", line.line, error), theme.error)
+        } else if line.matched.is_empty() {
+            (line.line.clone(), theme.text_dim)
+        } else {
+            (format!("{}  -> {}", line.line, line.matched.join(", ")), theme.ahead_gaining)
+        };
+        frame.line().span(color, format!(" {}", text));
+    }
+}
+
+/// Continue the autosplitters from the timer's split after it moved
+fn sync_split_index(timer: &Timer, watcher: &mut Option<LogWatcher>, memory: &mut Option<MemoryWatcher>) {
+    let index = timer.current_split_index().unwrap_or(0);
+    if let Some(ref mut w) = watcher {
+        w.set_split_index(index);
+    }
+    if let Some(ref mut m) = memory {
+        m.set_split_index(index);
+    }
+}
+
 fn render(
-    stdout: &mut std::io::Stdout,
+    frame: &mut Frame,
     timer: &Timer,
     splits_file: &SplitsFile,
-    watching: bool,
-) -> Result<(), Box<dyn std::error::Error>> {
-    execute!(stdout, cursor::MoveTo(0, 0), terminal::Clear(ClearType::All))?;
-
+    watcher: Option<&LogWatcher>,
+    memory: Option<&MemoryWatcher>,
+    log_status: Option<&str>,
+    view: &View,
+) {
+    let theme = view.theme;
+
+    let method = timer.current_timing_method();
     let snapshot = timer.snapshot();
-    let current_time = snapshot.current_time().real_time;
+    let current_time = snapshot.current_time()[method];
     let phase = timer.current_phase();
     let current_split_idx = timer.current_split_index().unwrap_or(0);
 
     // Header
-    execute!(
-        stdout,
-        SetForegroundColor(Color::Cyan),
-        Print(format!(" {} - {}\n", splits_file.game, splits_file.category)),
-        ResetColor,
-    )?;
+    frame
+        .line()
+        .span(theme.accent, format!(" {} - {}", splits_file.game, splits_file.category));
+
+    // Autosplitter info rows
+    if let Some(w) = watcher {
+        for (label, value) in w.info_rows() {
+            frame
+                .line()
+                .span(theme.text_dim, format!(" {}: ", label))
+                .span(theme.text, value);
+        }
+    }
 
-    // Splits list
-    execute!(stdout, Print("\n"))?;
+    // Deltas of the completed splits against the timer's comparison, the
+    // personal best or the sum of best segments, worked out and colored like
+    // the GUI's graph
     let run = timer.run();
-    for (i, split) in splits_file.splits.iter().enumerate() {
+    let comparison = timer.current_comparison();
+    let done = current_split_idx.min(run.len());
+    let split_times: Vec<_> = run.segments()[..done].iter().map(|s| s.split_time()[method]).collect();
+    let compared: Vec<_> = run.segments().iter().map(|s| s.comparison(comparison)[method]).collect();
+    let best_segments: Vec<_> = run.segments().iter().map(|s| s.best_segment_time()[method]).collect();
+    let line = graph_line(&split_times, &compared, &best_segments);
+
+    let mut deltas = vec![None; run.len()];
+    let mut previous_delta = None;
+    for (i, (delta, segment_delta)) in line.deltas.iter().zip(&line.segment_deltas).enumerate() {
+        let Some(delta) = *delta else {
+            continue; // Skipped, or nothing to compare with
+        };
+        let best_segment = segment_delta.is_some_and(|d| d < 0.0);
+        deltas[i] = Some((delta, theme.delta_color(DeltaKind::of(delta, previous_delta, best_segment))));
+        previous_delta = Some(delta);
+    }
+
+    // The current split shows its delta live once it is behind
+    if phase == TimerPhase::Running && current_split_idx < run.len() {
+        let compared = run.segment(current_split_idx).comparison(comparison)[method];
+        if let (Some(time), Some(compared)) = (current_time, compared) {
+            let delta = time.total_seconds() - compared.total_seconds();
+            if delta > 0.0 {
+                deltas[current_split_idx] = Some((delta, theme.behind_losing));
+            }
+        }
+    }
+
+    // Names take what the delta and time columns leave
+    let show_deltas = frame.width >= MIN_DELTA_WIDTH;
+    let name_width = frame
+        .width
+        .saturating_sub(BULLET_WIDTH + TIME_WIDTH + if show_deltas { DELTA_WIDTH } else { 0 });
+
+    // Splits list
+    frame.blank();
+    let rows = visible_splits(
+        splits_file.splits.len(),
+        current_split_idx,
+        view.visible_splits,
+        view.preview,
+        view.scroll,
+    );
+    for (row, &i) in rows.iter().enumerate() {
+        // Splits hidden before the final one
+        if row + 1 == rows.len() && row > 0 && rows[row - 1] + 1 < i {
+            frame.line().span(theme.text_dim, "    ⋮");
+        }
+
+        let split = &splits_file.splits[i];
         let segment = run.segment(i);
-        let split_time = segment.split_time().real_time;
 
         let (bullet, color) = if i < current_split_idx {
-            ("  • ✓", Color::Green)
+            ("  • ✓ ", theme.text)
         } else if i == current_split_idx && phase == TimerPhase::Running {
-            ("  • ▶", Color::Yellow)
+            ("  • ▶ ", theme.accent)
         } else {
-            ("  •  ", Color::DarkGrey)
+            ("  •   ", theme.text_dim)
         };
 
-        execute!(
-            stdout,
-            SetForegroundColor(color),
-            Print(format!("{} {:<28}", bullet, split.name)),
-        )?;
-
-        if i < current_split_idx {
-            execute!(
-                stdout,
-                Print(format!("  {}", format_time(split_time))),
-            )?;
-        }
+        // Split times once done, best segments until then
+        let (time, time_color) = if i < current_split_idx {
+            (format_time(segment.split_time()[method]), theme.text)
+        } else {
+            match segment.best_segment_time()[method] {
+                Some(best) => (format_time(Some(best)), theme.text_dim),
+                None => (String::new(), theme.text_dim),
+            }
+        };
 
-        execute!(stdout, ResetColor, Print("\n"))?;
+        let line = frame.line();
+        line.span(color, bullet).span(color, screen::fit(&subsplit_name(split), name_width));
+        if show_deltas {
+            match deltas[i] {
+                Some((delta, delta_color)) => {
+                    line.span(delta_color, format!("{:>w$}", format_delta(delta), w = DELTA_WIDTH))
+                }
+                None => line.span(color, " ".repeat(DELTA_WIDTH)),
+            };
+        }
+        line.span(time_color, format!("{:>w$}", time, w = TIME_WIDTH));
     }
 
     // Current time (big display)
-    execute!(stdout, Print("\n"))?;
-    
+    frame.blank();
+
     let time_color = match phase {
-        TimerPhase::NotRunning => Color::White,
-        TimerPhase::Running => Color::Green,
-        TimerPhase::Paused => Color::Yellow,
-        TimerPhase::Ended => Color::Cyan,
+        TimerPhase::NotRunning => theme.not_running,
+        TimerPhase::Running => theme.ahead_gaining,
+        TimerPhase::Paused => theme.paused,
+        TimerPhase::Ended => theme.personal_best,
     };
 
-    execute!(
-        stdout,
-        Print(" "),
-        SetForegroundColor(time_color),
-        Print(format!(" {} ", format_time(current_time))),
-        ResetColor,
-    )?;
-
     let status = match phase {
         TimerPhase::NotRunning => "[READY]",
         TimerPhase::Running => "[RUNNING]",
         TimerPhase::Paused => "[PAUSED]",
         TimerPhase::Ended => "[FINISHED]",
     };
-    execute!(stdout, Print(format!("  {}\n", status)))?;
+    frame
+        .line()
+        .span(time_color, format!("  {} ", format_time(current_time)))
+        .span(theme.text, format!("  {}", status));
 
     // Controls
-    execute!(
-        stdout,
-        SetForegroundColor(Color::DarkGrey),
-        Print(" [Space] Start/Split  [P] Pause  [R] Reset\n"),
-        Print(" [U] Undo split  [S] Skip split  [Q] Quit\n"),
-    )?;
-
-    if watching {
-        execute!(
-            stdout,
-            SetForegroundColor(Color::Magenta),
-            Print(" 👁 Auto-split active\n"),
-        )?;
+    frame.line().span(theme.text_dim, " [Space] Start/Split  [P] Pause  [R] Reset");
+    frame
+        .line()
+        .span(theme.text_dim, " [U] Undo split  [S] Skip split  [↑/↓] Scroll  [T] Theme  [Q] Quit");
+
+    if view.auto_split {
+        frame.line().span(theme.accent, " Auto-split active");
     }
 
-    execute!(stdout, ResetColor)?;
-    stdout.flush()?;
+    if let Some(m) = memory.filter(|m| !m.is_attached()) {
+        frame.line().span(theme.text_dim, format!(" Waiting for {} to start", m.process_name()));
+    }
 
-    Ok(())
-}
\ No newline at end of file
+    if let Some(error) = watcher.and_then(LogWatcher::script_error) {
+        frame.line().span(theme.error, format!(" {}", error));
+    }
+
+    if let Some(ref error) = view.wasm_error {
+        frame.line().span(theme.error, format!(" {}", error));
+    }
+
+    if let Some(message) = log_status {
+        frame.line().span(theme.error, format!(" {}", message));
+        frame.line().span(theme.text_dim, " Retrying every second, [A] Retry now");
+    }
+}
@@ -0,0 +1,201 @@
+use livesplit_auto_splitting::{
+    InterruptHandle, Runtime, SettingValue, SettingsStore, Timer as AutoSplitTimer, TimerState,
+};
+use crate::watcher::WatchEvent;
+use livesplit_core::{SharedTimer, TimerPhase};
+use std::fmt;
+use std::path::Path;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Instant;
+
+/// A boolean setting declared by a WebAssembly autosplitter
+#[derive(Debug, Clone)]
+pub struct WasmSetting {
+    pub key: String,
+    pub description: String,
+    pub value: bool,
+}
+
+enum Message {
+    SetSetting(String, bool),
+    Stop,
+}
+
+/// What the autosplitter asked of the timer, handed out by `poll`
+#[derive(Debug, Clone, Copy)]
+enum Request {
+    Start,
+    Split,
+    Reset,
+}
+
+/// A LiveSplit `.wasm` autosplitter running on the auto-splitting runtime
+/// livesplit-core is built on, in its own thread. Game time goes straight to
+/// the shared timer; starts, splits and resets come out of `poll` as the
+/// same events the other watchers produce, so they are recorded the same
+/// way. Autosplitters read their settings when they declare them, so
+/// changing one restarts the autosplitter with the new values.
+pub struct WasmAutosplitter {
+    sender: Sender<Message>,
+    settings: Arc<Mutex<Vec<WasmSetting>>>,
+    interrupt: Arc<Mutex<InterruptHandle>>,
+    requests: Arc<Mutex<Vec<Request>>>,
+    error: Arc<Mutex<Option<String>>>, // Why the autosplitter stopped
+}
+
+impl WasmAutosplitter {
+    pub fn load(path: &Path, timer: SharedTimer) -> Result<Self, Box<dyn std::error::Error>> {
+        let module = std::fs::read(path)?;
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let timer = Timer { timer, requests: Arc::clone(&requests) };
+        let runtime = Runtime::new(&module, timer.clone(), SettingsStore::new())?;
+
+        let (sender, receiver) = mpsc::channel();
+        let settings = Arc::new(Mutex::new(Vec::new()));
+        let interrupt = Arc::new(Mutex::new(runtime.interrupt_handle()));
+        let error = Arc::new(Mutex::new(None));
+
+        let thread_settings = Arc::clone(&settings);
+        let thread_interrupt = Arc::clone(&interrupt);
+        let thread_error = Arc::clone(&error);
+        thread::Builder::new()
+            .name("Autosplitter".into())
+            .spawn(move || {
+                let mut runtime = runtime;
+                let mut next_update = Instant::now();
+                loop {
+                    let wait = next_update.saturating_duration_since(Instant::now());
+                    match receiver.recv_timeout(wait) {
+                        Ok(Message::SetSetting(key, value)) => {
+                            let mut store = runtime.settings_store().clone();
+                            store.set(key.into(), SettingValue::Bool(value));
+                            // Keep the running autosplitter if it can't restart
+                            if let Ok(restarted) = Runtime::new(&module, timer.clone(), store) {
+                                runtime = restarted;
+                                *thread_interrupt.lock().unwrap() = runtime.interrupt_handle();
+                            }
+                        }
+                        Ok(Message::Stop) | Err(RecvTimeoutError::Disconnected) => return,
+                        Err(RecvTimeoutError::Timeout) => match runtime.update() {
+                            Ok(tick_rate) => next_update += tick_rate,
+                            // The autosplitter trapped or was interrupted
+                            Err(e) => {
+                                *thread_error.lock().unwrap() = Some(format!("Autosplitter stopped: {}", e));
+                                return;
+                            }
+                        },
+                    }
+                    *thread_settings.lock().unwrap() = settings_of(&runtime);
+                }
+            })?;
+
+        Ok(Self { sender, settings, interrupt, requests, error })
+    }
+
+    /// Starts, splits and resets asked for since the last poll. Splits are
+    /// numbered on from `split_index`, the split the timer is at.
+    pub fn poll(&mut self, split_index: usize) -> Vec<WatchEvent> {
+        let mut split_index = split_index;
+        self.requests
+            .lock()
+            .unwrap()
+            .drain(..)
+            .map(|request| match request {
+                Request::Start => WatchEvent::Start,
+                Request::Split => {
+                    split_index += 1;
+                    WatchEvent::Split(split_index - 1)
+                }
+                Request::Reset => WatchEvent::Reset,
+            })
+            .collect()
+    }
+
+    /// Why the autosplitter stopped running, if it did
+    pub fn error(&self) -> Option<String> {
+        self.error.lock().unwrap().clone()
+    }
+
+    pub fn settings(&self) -> Vec<WasmSetting> {
+        self.settings.lock().unwrap().clone()
+    }
+
+    pub fn set_setting(&self, key: &str, value: bool) {
+        let _ = self.sender.send(Message::SetSetting(key.to_string(), value));
+    }
+}
+
+impl Drop for WasmAutosplitter {
+    fn drop(&mut self) {
+        let _ = self.sender.send(Message::Stop);
+        // Stops an autosplitter stuck in its update
+        self.interrupt.lock().unwrap().interrupt();
+    }
+}
+
+/// The settings the autosplitter declared, with their stored or default value
+fn settings_of(runtime: &Runtime<Timer>) -> Vec<WasmSetting> {
+    let store = runtime.settings_store();
+    runtime
+        .user_settings()
+        .iter()
+        .map(|setting| {
+            let value = store.get(&setting.key).unwrap_or(&setting.default_value);
+            WasmSetting {
+                key: setting.key.to_string(),
+                description: setting.description.to_string(),
+                value: matches!(value, SettingValue::Bool(true)),
+            }
+        })
+        .collect()
+}
+
+/// The shared timer as the runtime sees it
+#[derive(Clone)]
+struct Timer {
+    timer: SharedTimer,
+    requests: Arc<Mutex<Vec<Request>>>,
+}
+
+impl AutoSplitTimer for Timer {
+    fn state(&self) -> TimerState {
+        match self.timer.read().unwrap().current_phase() {
+            TimerPhase::NotRunning => TimerState::NotRunning,
+            TimerPhase::Running => TimerState::Running,
+            TimerPhase::Paused => TimerState::Paused,
+            TimerPhase::Ended => TimerState::Ended,
+        }
+    }
+
+    fn start(&mut self) {
+        self.requests.lock().unwrap().push(Request::Start);
+    }
+
+    fn split(&mut self) {
+        self.requests.lock().unwrap().push(Request::Split);
+    }
+
+    fn reset(&mut self) {
+        self.requests.lock().unwrap().push(Request::Reset);
+    }
+
+    fn set_game_time(&mut self, time: time::Duration) {
+        self.timer.write().unwrap().set_game_time(time.into());
+    }
+
+    fn pause_game_time(&mut self) {
+        self.timer.write().unwrap().pause_game_time();
+    }
+
+    fn resume_game_time(&mut self) {
+        self.timer.write().unwrap().resume_game_time();
+    }
+
+    fn set_variable(&mut self, key: &str, value: &str) {
+        self.timer.write().unwrap().set_custom_variable(key, value);
+    }
+
+    fn log(&mut self, _message: fmt::Arguments<'_>) {}
+}
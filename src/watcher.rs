@@ -1,28 +1,161 @@
+use crate::script::{Action, ScriptAutosplitter};
+use crate::splits::SplitsFile;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
 use std::fs::File;
-use std::io::{BufRead, BufReader, Seek, SeekFrom};
-use std::path::PathBuf;
+use std::io::{BufRead, BufReader, ErrorKind, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
 
 pub enum WatchEvent {
     Start,
     Split(usize), // Index of split triggered
     Reset,
+    Pause,
+    Resume,
+    Skip(usize), // Index of split skipped
+    Ended(Ending),
+}
+
+/// Lines kept for the debug console
+const DEBUG_LINES: usize = 200;
+
+/// A log line as the watcher saw it, for the debug console
+#[derive(Debug, Clone)]
+pub struct DebugLine {
+    pub line: String,
+    pub matched: Vec<String>, // What each event the line produced was
+    pub error: Option<String>, // Why the script failed on the line
+}
+
+/// Ways a run can end before its final split
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ending {
+    Death,
+    Quit,   // Quit to menu
+    NewRun, // A new run started over the current one
+}
+
+/// A state guard: only lets a trigger fire if the most recent log line
+/// containing `line` (or the current value of variable `var`) contains
+/// `contains`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Guard {
+    #[serde(default)]
+    pub line: Option<String>,
+    #[serde(default)]
+    pub var: Option<String>,
+    pub contains: String,
+}
+
+impl Guard {
+    /// A guard looks at either the last line containing `line` or a variable
+    pub fn check(&self) -> Result<(), String> {
+        match (&self.line, &self.var) {
+            (Some(_), None) | (None, Some(_)) => Ok(()),
+            (None, None) => Err(format!("guard for {:?} needs a `line` or a `var`", self.contains)),
+            (Some(_), Some(_)) => Err(format!(
+                "guard for {:?} has both a `line` and a `var`, it can only look at one",
+                self.contains
+            )),
+        }
+    }
+}
+
+/// A value captured from log lines, e.g. the current seed or character.
+/// The value is the text following `after` (or `line` if unset), cut off at
+/// `until` if given.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Variable {
+    pub name: String,
+    pub line: String,
+    #[serde(default)]
+    pub after: Option<String>,
+    #[serde(default)]
+    pub until: Option<String>,
+    #[serde(default)]
+    pub label: Option<String>, // Shown as an info row when set
+}
+
+impl Variable {
+    fn capture(&self, line: &str) -> Option<String> {
+        if !line.contains(self.line.as_str()) {
+            return None;
+        }
+
+        let after = self.after.as_deref().unwrap_or(self.line.as_str());
+        let start = line.find(after)? + after.len();
+        let rest = &line[start..];
+        let value = match self.until {
+            Some(ref until) => rest.split(until.as_str()).next().unwrap_or(rest),
+            None => rest,
+        };
+
+        Some(value.trim().to_string())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Trigger {
+    pub keyword: String,
+    pub unless: Vec<String>, // Lines containing any of these never fire
+    pub guards: Vec<Guard>,
+}
+
+impl Trigger {
+    pub fn new(keyword: String, unless: Vec<String>, guards: Vec<Guard>) -> Self {
+        Self { keyword, unless, guards }
+    }
+
+    fn matches(
+        &self,
+        line: &str,
+        last_seen: &HashMap<String, String>,
+        values: &HashMap<String, String>,
+    ) -> bool {
+        if !line.contains(self.keyword.as_str()) {
+            return false;
+        }
+
+        if self.unless.iter().any(|u| line.contains(u.as_str())) {
+            return false;
+        }
+
+        self.guards.iter().all(|guard| {
+            let seen = match (&guard.var, &guard.line) {
+                (Some(var), _) => values.get(var),
+                (None, Some(keyword)) => last_seen.get(keyword),
+                (None, None) => None,
+            };
+            seen.map(|s| s.contains(guard.contains.as_str()))
+                .unwrap_or(false)
+        })
+    }
 }
 
 pub struct LogWatcher {
     path: PathBuf,
     reader: BufReader<File>,
-    start_trigger: Option<String>,
-    reset_trigger: Option<String>,
-    split_triggers: Vec<Option<String>>,
+    start_trigger: Option<Trigger>,
+    reset_trigger: Option<Trigger>,
+    split_triggers: Vec<Option<Trigger>>,
     current_split: usize,
+    last_seen: HashMap<String, String>, // Guard keyword -> last line containing it
+    variables: Vec<Variable>,
+    values: HashMap<String, String>, // Variable name -> last captured value
+    script: Option<ScriptAutosplitter>,
+    ending_triggers: Vec<(Ending, Trigger)>,
+    debug: Option<VecDeque<DebugLine>>,
+    recorded: Option<Vec<String>>, // Raw lines since the last `take_recorded`
+    script_error: Option<String>, // Latest script failure, kept until the log is reattached
 }
 
 impl LogWatcher {
     pub fn new(
         path: PathBuf,
-        start_trigger: Option<String>,
-        reset_trigger: Option<String>,
-        split_triggers: Vec<Option<String>>,
+        start_trigger: Option<Trigger>,
+        reset_trigger: Option<Trigger>,
+        split_triggers: Vec<Option<Trigger>>,
+        variables: Vec<Variable>,
     ) -> Result<Self, std::io::Error> {
         let file = File::open(&path)?;
         let mut reader = BufReader::new(file);
@@ -37,17 +170,129 @@ impl LogWatcher {
             reset_trigger,
             split_triggers,
             current_split: 0,
+            last_seen: HashMap::new(),
+            variables,
+            values: HashMap::new(),
+            script: None,
+            ending_triggers: Vec::new(),
+            debug: None,
+            recorded: None,
+            script_error: None,
         })
     }
 
-    pub fn reset_split_index(&mut self) {
-        self.current_split = 0;
+    /// Watch `path` with the triggers of a splits file, or the script in
+    /// `script_dir` if there is one. Errors say what is wrong for display.
+    pub fn for_splits(
+        path: PathBuf,
+        splits_file: &SplitsFile,
+        script_dir: Option<&Path>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let watcher = Self::new(
+            path.clone(),
+            splits_file.start_watch_trigger(),
+            splits_file.reset_watch_trigger(),
+            splits_file.split_watch_triggers(),
+            splits_file.variables.clone(),
+        )
+        .map_err(|e| match e.kind() {
+            ErrorKind::NotFound => format!("Log not found: {}", path.display()),
+            ErrorKind::PermissionDenied => format!("Permission denied reading log: {}", path.display()),
+            _ => format!("Could not open log {}: {}", path.display(), e),
+        })?
+        .with_endings(splits_file.ending_watch_triggers());
+
+        let script = match script_dir {
+            Some(dir) => ScriptAutosplitter::load_for_dir(dir, splits_file)
+                .map_err(|e| format!("Bad autosplitter script: {}", e))?,
+            None => None,
+        };
+
+        Ok(match script {
+            Some(script) => watcher.with_script(script),
+            None => watcher,
+        })
+    }
+
+    pub fn with_endings(mut self, ending_triggers: Vec<(Ending, Trigger)>) -> Self {
+        self.ending_triggers = ending_triggers;
+        self
+    }
+
+    /// Let a script decide on actions instead of the keyword triggers
+    pub fn with_script(mut self, script: ScriptAutosplitter) -> Self {
+        self.script = Some(script);
+        self
+    }
+
+    /// Keep the most recent lines and what they matched for `debug_lines`
+    pub fn with_debug(mut self) -> Self {
+        self.debug = Some(VecDeque::with_capacity(DEBUG_LINES));
+        self
+    }
+
+    /// Keep every raw line read, for recording sessions
+    pub fn with_recording(mut self) -> Self {
+        self.recorded = Some(Vec::new());
+        self
+    }
+
+    pub fn take_recorded(&mut self) -> Vec<String> {
+        self.recorded.as_mut().map(std::mem::take).unwrap_or_default()
+    }
+
+    pub fn debug_lines(&self) -> impl Iterator<Item = &DebugLine> {
+        self.debug.iter().flatten()
+    }
+
+    pub fn split_index(&self) -> usize {
+        self.current_split
+    }
+
+    /// What the watcher needs to see next to split
+    pub fn waiting_for(&self) -> String {
+        if self.script.is_some() {
+            return "Script decides".to_string();
+        }
+        match self.split_triggers.get(self.current_split) {
+            Some(Some(trigger)) => format!("Split {}: \"{}\"", self.current_split + 1, trigger.keyword),
+            Some(None) => format!("Split {}: manual", self.current_split + 1),
+            None => "Last split done".to_string(),
+        }
     }
 
     pub fn set_split_index(&mut self, index: usize) {
         self.current_split = index;
     }
 
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(|v| v.as_str())
+    }
+
+    /// Why the script last failed on a line, for display
+    pub fn script_error(&self) -> Option<&str> {
+        self.script_error.as_deref()
+    }
+
+    /// Labelled variables and script info with their current values, for display
+    pub fn info_rows(&self) -> Vec<(String, String)> {
+        let mut rows: Vec<(String, String)> = self
+            .variables
+            .iter()
+            .filter_map(|v| {
+                let label = v.label.clone()?;
+                let value = self.value(&v.name).unwrap_or("-").to_string();
+                Some((label, value))
+            })
+            .collect();
+
+        if let Some(ref script) = self.script {
+            rows.extend(script.info_rows());
+        }
+
+        rows
+    }
+
     pub fn poll(&mut self) -> Vec<WatchEvent> {
         let mut events = Vec::new();
         let mut line = String::new();
@@ -67,40 +312,343 @@ impl LogWatcher {
             line.clear();
             match self.reader.read_line(&mut line) {
                 Ok(0) => break, // No more data
-                Ok(_) => {
-                    let line = line.trim();
-                    
-                    // Check for reset trigger first
-                    if let Some(ref trigger) = self.reset_trigger {
-                        if line.contains(trigger.as_str()) {
-                            events.push(WatchEvent::Reset);
-                            self.current_split = 0;
-                            continue;
-                        }
-                    }
-
-                    // Check for start trigger
-                    if let Some(ref trigger) = self.start_trigger {
-                        if line.contains(trigger.as_str()) {
-                            events.push(WatchEvent::Start);
-                            continue;
-                        }
-                    }
-
-                    // Check for current split trigger
-                    if self.current_split < self.split_triggers.len() {
-                        if let Some(ref trigger) = self.split_triggers[self.current_split] {
-                            if line.contains(trigger.as_str()) {
-                                events.push(WatchEvent::Split(self.current_split));
-                                self.current_split += 1;
-                            }
-                        }
-                    }
-                }
+                Ok(_) => events.extend(self.process_line(line.trim())),
                 Err(_) => break,
             }
         }
 
         events
     }
+
+    fn process_line(&mut self, line: &str) -> Vec<WatchEvent> {
+        if let Some(ref mut recorded) = self.recorded {
+            recorded.push(line.to_string());
+        }
+
+        for variable in &self.variables {
+            if let Some(value) = variable.capture(line) {
+                self.values.insert(variable.name.clone(), value);
+            }
+        }
+
+        let mut error = None;
+        let events: Vec<WatchEvent> = if self.script.is_some() {
+            // A failing script produces no actions for this line
+            self.run_script(line).unwrap_or_else(|e| {
+                error = Some(format!("Autosplitter script failed: {}", e));
+                Vec::new()
+            })
+        } else {
+            self.match_triggers(line).into_iter().collect()
+        };
+
+        if self.debug.is_some() {
+            let matched = events.iter().map(|e| self.describe(e)).collect();
+            if let Some(ref mut debug) = self.debug {
+                if debug.len() == DEBUG_LINES {
+                    debug.pop_front();
+                }
+                debug.push_back(DebugLine { line: line.to_string(), matched, error: error.clone() });
+            }
+        }
+        if error.is_some() {
+            self.script_error = error;
+        }
+
+        events
+    }
+
+    fn describe(&self, event: &WatchEvent) -> String {
+        let keyword = |trigger: Option<&Trigger>| match trigger {
+            Some(t) if self.script.is_none() => format!(" (\"{}\")", t.keyword),
+            _ => String::new(),
+        };
+
+        match event {
+            WatchEvent::Start => format!("Start{}", keyword(self.start_trigger.as_ref())),
+            WatchEvent::Split(i) => format!(
+                "Split {}{}",
+                i + 1,
+                keyword(self.split_triggers.get(*i).and_then(|t| t.as_ref()))
+            ),
+            WatchEvent::Reset => format!("Reset{}", keyword(self.reset_trigger.as_ref())),
+            WatchEvent::Pause => "Pause".to_string(),
+            WatchEvent::Resume => "Resume".to_string(),
+            WatchEvent::Skip(i) => format!("Skip {}", i + 1),
+            WatchEvent::Ended(ending) => format!(
+                "{:?}{}",
+                ending,
+                keyword(self.ending_triggers.iter().find(|(e, _)| e == ending).map(|(_, t)| t))
+            ),
+        }
+    }
+
+    fn run_script(&mut self, line: &str) -> Result<Vec<WatchEvent>, Box<dyn std::error::Error>> {
+        let Some(ref mut script) = self.script else {
+            return Ok(Vec::new());
+        };
+
+        let actions = script.on_line(line, self.current_split)?;
+
+        Ok(actions
+            .into_iter()
+            .map(|action| match action {
+                Action::Start => WatchEvent::Start,
+                Action::Split => {
+                    self.current_split += 1;
+                    WatchEvent::Split(self.current_split - 1)
+                }
+                Action::Reset => {
+                    self.current_split = 0;
+                    WatchEvent::Reset
+                }
+                Action::Pause => WatchEvent::Pause,
+                Action::Resume => WatchEvent::Resume,
+                Action::Skip => {
+                    self.current_split += 1;
+                    WatchEvent::Skip(self.current_split - 1)
+                }
+                Action::Death => WatchEvent::Ended(Ending::Death),
+                Action::Quit => WatchEvent::Ended(Ending::Quit),
+                Action::NewRun => WatchEvent::Ended(Ending::NewRun),
+            })
+            .collect())
+    }
+
+    fn match_triggers(&mut self, line: &str) -> Option<WatchEvent> {
+        self.remember_guarded_line(line);
+
+        // Run endings take precedence over the plain reset trigger
+        for (ending, trigger) in &self.ending_triggers {
+            if trigger.matches(line, &self.last_seen, &self.values) {
+                return Some(WatchEvent::Ended(*ending));
+            }
+        }
+
+        // Check for reset trigger first
+        if let Some(ref trigger) = self.reset_trigger {
+            if trigger.matches(line, &self.last_seen, &self.values) {
+                self.current_split = 0;
+                return Some(WatchEvent::Reset);
+            }
+        }
+
+        // Check for start trigger
+        if let Some(ref trigger) = self.start_trigger {
+            if trigger.matches(line, &self.last_seen, &self.values) {
+                return Some(WatchEvent::Start);
+            }
+        }
+
+        // Check for current split trigger
+        if let Some(Some(ref trigger)) = self.split_triggers.get(self.current_split) {
+            if trigger.matches(line, &self.last_seen, &self.values) {
+                let index = self.current_split;
+                self.current_split += 1;
+                return Some(WatchEvent::Split(index));
+            }
+        }
+
+        None
+    }
+
+    /// Record this line against every guard keyword it contains, so guards
+    /// can later check what the most recent matching line said.
+    fn remember_guarded_line(&mut self, line: &str) {
+        let guards = self
+            .start_trigger
+            .iter()
+            .chain(self.reset_trigger.iter())
+            .chain(self.split_triggers.iter().flatten())
+            .chain(self.ending_triggers.iter().map(|(_, t)| t))
+            .flat_map(|t| t.guards.iter());
+
+        for keyword in guards.filter_map(|g| g.line.as_ref()) {
+            if line.contains(keyword.as_str()) {
+                self.last_seen.insert(keyword.clone(), line.to_string());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn watcher_with(
+        name: &str,
+        split_triggers: Vec<Option<Trigger>>,
+        variables: Vec<Variable>,
+    ) -> LogWatcher {
+        let path = std::env::temp_dir().join(format!("macsplit-watcher-{}.log", name));
+        std::fs::File::create(&path).unwrap().write_all(b"").unwrap();
+        LogWatcher::new(path, None, None, split_triggers, variables).unwrap()
+    }
+
+    fn split_index(events: Vec<WatchEvent>) -> Option<usize> {
+        events.into_iter().find_map(|e| match e {
+            WatchEvent::Split(i) => Some(i),
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn test_plain_trigger_splits_in_order() {
+        let trigger = Trigger::new("m_Stage".to_string(), vec![], vec![]);
+        let mut watcher = watcher_with("plain", vec![Some(trigger.clone()), Some(trigger)], vec![]);
+
+        assert_eq!(split_index(watcher.process_line("Level::Init m_Stage 1")), Some(0));
+        assert_eq!(split_index(watcher.process_line("Level::Init m_Stage 2")), Some(1));
+        assert_eq!(split_index(watcher.process_line("Level::Init m_Stage 3")), None);
+    }
+
+    #[test]
+    fn test_unless_suppresses_trigger() {
+        let trigger = Trigger::new("m_Stage".to_string(), vec!["Continue".to_string()], vec![]);
+        let mut watcher = watcher_with("unless", vec![Some(trigger)], vec![]);
+
+        assert_eq!(split_index(watcher.process_line("Continue game, m_Stage 2")), None);
+        assert_eq!(split_index(watcher.process_line("Level::Init m_Stage 2")), Some(0));
+    }
+
+    #[test]
+    fn test_guard_requires_last_seen_line() {
+        let guard = Guard {
+            line: Some("Initialized player".to_string()),
+            var: None,
+            contains: "Isaac".to_string(),
+        };
+        let trigger = Trigger::new("m_Stage".to_string(), vec![], vec![guard]);
+        let mut watcher = watcher_with("guard", vec![Some(trigger)], vec![]);
+
+        // No character line seen yet
+        assert_eq!(split_index(watcher.process_line("Level::Init m_Stage 1")), None);
+
+        watcher.process_line("Initialized player Magdalene");
+        assert_eq!(split_index(watcher.process_line("Level::Init m_Stage 1")), None);
+
+        watcher.process_line("Initialized player Isaac");
+        assert_eq!(split_index(watcher.process_line("Level::Init m_Stage 1")), Some(0));
+    }
+
+    fn seed_variable() -> Variable {
+        Variable {
+            name: "seed".to_string(),
+            line: "RNG Start Seed:".to_string(),
+            after: None,
+            until: Some("(".to_string()),
+            label: Some("Seed".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_variable_captured_from_line() {
+        let mut watcher = watcher_with("variable", vec![], vec![seed_variable()]);
+
+        assert_eq!(watcher.value("seed"), None);
+        watcher.process_line("[INFO] - RNG Start Seed: ABCD 1234 (1234567)");
+        assert_eq!(watcher.value("seed"), Some("ABCD 1234"));
+        assert_eq!(
+            watcher.info_rows(),
+            vec![("Seed".to_string(), "ABCD 1234".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_guard_on_variable() {
+        let character = Variable {
+            name: "character".to_string(),
+            line: "Initialized player with Variant".to_string(),
+            after: Some("Subtype ".to_string()),
+            until: None,
+            label: None,
+        };
+        let guard = Guard {
+            line: None,
+            var: Some("character".to_string()),
+            contains: "0".to_string(),
+        };
+        let trigger = Trigger::new("m_Stage".to_string(), vec![], vec![guard]);
+        let mut watcher = watcher_with("var-guard", vec![Some(trigger)], vec![character]);
+
+        watcher.process_line("Initialized player with Variant 0 and Subtype 1");
+        assert_eq!(split_index(watcher.process_line("Level::Init m_Stage 1")), None);
+
+        watcher.process_line("Initialized player with Variant 0 and Subtype 0");
+        assert_eq!(split_index(watcher.process_line("Level::Init m_Stage 1")), Some(0));
+    }
+
+    #[test]
+    fn test_script_replaces_triggers() {
+        let script = ScriptAutosplitter::compile(
+            r#"fn on_line(line, split_index) {
+                if line.contains("m_Stage") && split_index < 2 { return "split"; }
+                if line.contains("Curse") { return "skip"; }
+            }"#,
+        ).unwrap();
+        let trigger = Trigger::new("never".to_string(), vec![], vec![]);
+        let mut watcher = watcher_with("script", vec![Some(trigger)], vec![]).with_script(script);
+
+        assert_eq!(split_index(watcher.process_line("Level::Init m_Stage 1")), Some(0));
+        assert!(matches!(watcher.process_line("Curse of the Lost")[..], [WatchEvent::Skip(1)]));
+        assert_eq!(split_index(watcher.process_line("Level::Init m_Stage 3")), None);
+    }
+
+    #[test]
+    fn test_script_errors_are_kept() {
+        let script = ScriptAutosplitter::compile(
+            r#"fn on_line(line, split_index) {
+                if line.contains("Boss") { return "explode"; }
+            }"#,
+        ).unwrap();
+        let mut watcher = watcher_with("script-error", vec![None], vec![]).with_script(script).with_debug();
+
+        assert!(watcher.process_line("Boss").is_empty());
+        watcher.process_line("Level::Init m_Stage 2");
+        assert_eq!(watcher.script_error(), Some("Autosplitter script failed: Unknown autosplitter action: explode"));
+
+        let lines: Vec<_> = watcher.debug_lines().collect();
+        assert_eq!(lines[0].error.as_deref(), Some("Autosplitter script failed: Unknown autosplitter action: explode"));
+        assert_eq!(lines[1].error, None);
+    }
+
+    #[test]
+    fn test_ending_triggers() {
+        let split = Trigger::new("m_Stage".to_string(), vec![], vec![]);
+        let mut watcher = watcher_with("endings", vec![Some(split)], vec![]).with_endings(vec![
+            (Ending::Death, Trigger::new("Game Over".to_string(), vec![], vec![])),
+            (Ending::Quit, Trigger::new("Menu Title Init".to_string(), vec![], vec![])),
+        ]);
+
+        assert!(matches!(watcher.process_line("Game Over")[..], [WatchEvent::Ended(Ending::Death)]));
+        assert!(matches!(watcher.process_line("Menu Title Init")[..], [WatchEvent::Ended(Ending::Quit)]));
+        assert_eq!(split_index(watcher.process_line("Level::Init m_Stage 1")), Some(0));
+    }
+
+    #[test]
+    fn test_for_splits_reports_missing_log() {
+        let path = std::env::temp_dir().join("macsplit-missing.log");
+        let error = LogWatcher::for_splits(path.clone(), &SplitsFile::default_run(), None)
+            .err()
+            .unwrap();
+        assert_eq!(error.to_string(), format!("Log not found: {}", path.display()));
+    }
+
+    #[test]
+    fn test_debug_lines_record_matches() {
+        let split = Trigger::new("m_Stage".to_string(), vec![], vec![]);
+        let mut watcher = watcher_with("debug", vec![Some(split), None], vec![]).with_debug();
+        assert_eq!(watcher.waiting_for(), "Split 1: \"m_Stage\"");
+
+        watcher.process_line("Loading room");
+        watcher.process_line("Level::Init m_Stage 2");
+
+        let lines: Vec<_> = watcher.debug_lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].matched.is_empty());
+        assert_eq!(lines[1].matched, vec!["Split 1 (\"m_Stage\")".to_string()]);
+        assert_eq!(watcher.split_index(), 1);
+        assert_eq!(watcher.waiting_for(), "Split 2: manual");
+    }
 }